@@ -454,6 +454,54 @@ impl<const N: usize> Num<i32, N> {
         }
         Self(c << (N / 2))
     }
+
+    #[must_use]
+    /// Calculates the angle of the vector (x, self) as a number of revolutions
+    /// in the range [0, 1), making it the inverse of [Vector2D::new_from_angle].
+    /// This uses a polynomial approximation so is accurate to within about
+    /// 0.0003 revolutions, notwithstanding the precision of `N` itself.
+    /// ```
+    /// # use agb_fixnum::*;
+    /// let y: Num<i32, 16> = num!(1.);
+    /// assert_eq!(y.atan2(num!(0.)), num!(0.25));
+    /// let y: Num<i32, 16> = num!(0.);
+    /// assert_eq!(y.atan2(num!(-1.)), num!(0.5));
+    /// ```
+    pub fn atan2(self, x: Self) -> Self {
+        if self.0 == 0 && x.0 == 0 {
+            return Self(0);
+        }
+
+        let one: Self = 1.into();
+        let flip = self.0.abs() > x.0.abs();
+        let (numerator, denominator) = if flip {
+            (x.0.abs(), self.0.abs())
+        } else {
+            (self.0.abs(), x.0.abs())
+        };
+
+        // Work in i64s to avoid the multiplications overflowing for large N
+        let mul = |a: i64, b: i64| (a * b) >> N;
+        let raw_one = i64::from(one.0);
+
+        // ratio in [0, 1]
+        let z = (i64::from(numerator) << N) / i64::from(denominator);
+
+        // atan(z) / 2pi for z in [0, 1]
+        let c1 = i64::from(Self::new_from_parts(num_inner!(0.038945)).0);
+        let c2 = i64::from(Self::new_from_parts(num_inner!(0.010552)).0);
+        let octant = Self((z / 8 - mul(mul(z, z - raw_one), c1 + mul(z, c2))) as i32);
+
+        let quadrant = if flip { one / 4 - octant } else { octant };
+        let half = if x.0 < 0 {
+            one / 2 - quadrant
+        } else {
+            quadrant
+        };
+        let angle = if self.0 < 0 { -half } else { half };
+
+        angle.rem_euclid(one)
+    }
 }
 
 impl<I: FixedWidthSignedInteger, const N: usize> Num<I, N> {
@@ -1237,6 +1285,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn atan2_accuracy() {
+        let cases: [((i32, i32), Num<i32, 16>); 4] = [
+            ((3, 4), num!(0.147584)),
+            ((-3, 4), num!(0.352416)),
+            ((-4, -3), num!(0.602416)),
+            ((4, -3), num!(0.897584)),
+        ];
+
+        for ((x, y), expected) in cases {
+            let result = Num::<i32, 16>::new(y).atan2(x.into());
+            assert!(
+                (result - expected).abs() < num!(0.0003),
+                "({}, {}) gave {}, expected {}",
+                x,
+                y,
+                result,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn atan2_of_diagonals() {
+        let one: Num<i32, 16> = 1.into();
+
+        assert_eq!(one.atan2(one), num!(0.125));
+        assert_eq!(one.atan2(-one), num!(0.375));
+        assert_eq!((-one).atan2(-one), num!(0.625));
+        assert_eq!((-one).atan2(one), num!(0.875));
+    }
+
     #[test]
     fn test_only_frac_bits() {
         let quarter: Num<u8, 8> = num!(0.25);
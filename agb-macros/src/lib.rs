@@ -101,6 +101,54 @@ pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Registers a test just like `#[test_case]`, optionally with a maximum number
+/// of cycles it may take, as in `#[agb::test_case(max_cycles = 10_000)]`. The
+/// budget is passed to the test runner along with the test, which fails the
+/// test in `mgba-test-runner` if it takes longer than that.
+#[proc_macro_attribute]
+pub fn test_case(args: TokenStream, input: TokenStream) -> TokenStream {
+    let input = proc_macro2::TokenStream::from(input);
+
+    if args.is_empty() {
+        return quote!(
+            #[::core::prelude::v1::test_case]
+            #input
+        )
+        .into();
+    }
+
+    let budget: syn::MetaNameValue =
+        syn::parse(args).expect("expected #[agb::test_case(max_cycles = <number>)]");
+    assert!(
+        budget.path.is_ident("max_cycles"),
+        "the only argument #[agb::test_case] takes is max_cycles"
+    );
+    let max_cycles: u32 = match budget.lit {
+        syn::Lit::Int(max_cycles) => max_cycles
+            .base10_parse()
+            .expect("max_cycles should fit in a u32"),
+        _ => panic!("max_cycles should be a number of cycles"),
+    };
+
+    let f: ItemFn = syn::parse2(input).expect("#[agb::test_case] must be applied to a function");
+    let name = &f.sig.ident;
+    let registered = Ident::new(&format!("_agb_cycle_budget_{}", name), Span::call_site());
+
+    quote!(
+        #[cfg(test)]
+        #f
+
+        #[::core::prelude::v1::test_case]
+        #[allow(non_upper_case_globals)]
+        static #registered: agb::test_runner::CycleBudgeted = agb::test_runner::CycleBudgeted {
+            name: concat!(module_path!(), "::", stringify!(#name)),
+            test: #name,
+            max_cycles: #max_cycles,
+        };
+    )
+    .into()
+}
+
 #[proc_macro]
 pub fn num(input: TokenStream) -> TokenStream {
     let f = syn::parse_macro_input!(input as syn::LitFloat);
@@ -73,4 +73,23 @@ fn main() {
     );
 
     println!("cargo:rustc-link-search={out_dir}");
+
+    write_sin_degrees_table(&out_dir);
+}
+
+/// The sine of every whole degree with 8 fractional bits, used by
+/// `AffineMatrix::from_rotation_deg_lut`
+fn write_sin_degrees_table(out_dir: &str) {
+    let table: Vec<String> = (0..360)
+        .map(|degrees| {
+            let sin = f64::from(degrees).to_radians().sin();
+            ((sin * 256.0).round() as i16).to_string()
+        })
+        .collect();
+
+    std::fs::write(
+        format!("{out_dir}/sin_degrees.rs"),
+        format!("[{}]", table.join(", ")),
+    )
+    .expect("failed to write sin table");
 }
@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+// Left and right rotate the background about the middle of the screen, and up
+// and down zoom in and out.
+
+use agb::{
+    display::{
+        affine::{AffineMatrixBackground, BackgroundLayer},
+        tiled::{AffineBackgroundSize, TileFormat, TileSet, TiledMap},
+        Priority, HEIGHT, WIDTH,
+    },
+    fixnum::{num, Num},
+    include_gfx,
+    input::Tri,
+};
+
+include_gfx!("examples/affine_tiles.toml");
+
+#[agb::entry]
+fn main(mut gba: agb::Gba) -> ! {
+    let (gfx, mut vram) = gba.display.video.tiled2();
+    let vblank = agb::interrupt::VBlank::get();
+
+    let tileset = TileSet::new(affine_tiles::water_tiles.tiles, TileFormat::EightBpp);
+
+    vram.set_background_palettes(affine_tiles::PALETTES);
+
+    // the first affine background in tiled mode 2 is background 2
+    let mut bg = gfx.background(Priority::P0, AffineBackgroundSize::Background32x32);
+
+    for y in 0..32u16 {
+        for x in 0..32u16 {
+            bg.set_tile(&mut vram, (x, y).into(), &tileset, 1);
+        }
+    }
+
+    bg.commit(&mut vram);
+    bg.show();
+
+    let mut input = agb::input::ButtonController::new();
+
+    let mut rotation: Num<i32, 16> = num!(0.);
+    let mut zoom: Num<i32, 8> = num!(1.);
+
+    let texture_centre = (num!(128.), num!(128.)).into();
+    let screen_centre = (Num::new(WIDTH / 2), Num::new(HEIGHT / 2)).into();
+
+    loop {
+        input.update();
+
+        rotation += Num::<i32, 16>::new(input.x_tri() as i32) / 128;
+        rotation = rotation.rem_euclid(1.into());
+
+        zoom = match input.y_tri() {
+            Tri::Negative => zoom * num!(1.03125),
+            Tri::Positive => zoom / num!(1.03125),
+            Tri::Zero => zoom,
+        };
+        zoom = zoom.clamp(num!(0.25), num!(4.));
+
+        let transformation = AffineMatrixBackground::from_scale_rotation_position(
+            texture_centre,
+            (zoom, zoom).into(),
+            rotation,
+            screen_centre,
+        );
+
+        vblank.wait_for_vblank();
+        // the tiles were committed already, so only the matrix needs updating
+        transformation.commit_to_bg(BackgroundLayer::Bg2);
+    }
+}
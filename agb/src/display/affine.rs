@@ -83,10 +83,21 @@
 
 use core::{
     convert::TryFrom,
+    hash::{Hash, Hasher},
+    iter::Product,
+    marker::PhantomData,
     ops::{Mul, MulAssign},
 };
 
-use agb_fixnum::{Num, Vector2D};
+use agb_fixnum::{Num, Rect, Vector2D};
+use alloc::vec::Vec;
+
+use super::{object::ObjectController, HEIGHT, WIDTH};
+use crate::{
+    dma,
+    memory_mapped::{MemoryMapped, MemoryMapped1DArray},
+    sync::Static,
+};
 
 type AffineMatrixElement = Num<i32, 8>;
 
@@ -123,8 +134,17 @@ impl AffineMatrix {
     }
 
     #[must_use]
-    /// Generates the matrix that represents a rotation
+    /// Generates the matrix that represents a rotation. Fixed point numbers have
+    /// no negative zero, so a rotation by a whole number of turns is exactly
+    /// equal to the [identity][AffineMatrix::identity]. In debug builds this
+    /// panics if the angle is too many turns to be sensible, which usually
+    /// means it overflowed while being calculated.
     pub fn from_rotation<const N: usize>(angle: Num<i32, N>) -> Self {
+        debug_assert!(
+            is_sane(angle),
+            "angle {angle:?} is too large, did it overflow?"
+        );
+
         fn from_rotation(angle: Num<i32, 8>) -> AffineMatrix {
             let cos = angle.cos().change_base();
             let sin = angle.sin().change_base();
@@ -141,7 +161,7 @@ impl AffineMatrix {
                 y: 0.into(),
             }
         }
-        from_rotation(angle.rem_euclid(1.into()).change_base())
+        from_rotation(wrap_angle(angle).change_base())
     }
 
     // Identity for rotation / scale / skew
@@ -158,12 +178,42 @@ impl AffineMatrix {
         }
     }
 
+    /// Attempts to generate the matrix that represents a translation by the
+    /// position, failing if the translation is too large for a background. The
+    /// translation registers of a background are 28 bit signed numbers with 8
+    /// fractional bits, so can only represent around ±524288 pixels.
+    pub fn try_from_translation(position: Vector2D<Num<i32, 8>>) -> Result<Self, OverflowError> {
+        const LIMIT: i32 = 1 << 27;
+        // the translation is stored negated, so must be in (-LIMIT, LIMIT]
+        let fits = |n: Num<i32, 8>| n.to_raw() > -LIMIT && n.to_raw() <= LIMIT;
+
+        if fits(position.x) && fits(position.y) {
+            Ok(Self::from_translation(position))
+        } else {
+            Err(OverflowError(()))
+        }
+    }
+
     #[must_use]
     /// The position fields of the matrix
     pub fn position(&self) -> Vector2D<Num<i32, 8>> {
         (-self.x, -self.y).into()
     }
 
+    #[must_use]
+    /// The first column of the matrix, `(a, c)`, which is what a step of one
+    /// pixel along the x axis of the screen becomes after transformation.
+    pub fn x_axis(&self) -> Vector2D<Num<i32, 8>> {
+        (self.a, self.c).into()
+    }
+
+    #[must_use]
+    /// The second column of the matrix, `(b, d)`, which is what a step of one
+    /// pixel along the y axis of the screen becomes after transformation.
+    pub fn y_axis(&self) -> Vector2D<Num<i32, 8>> {
+        (self.b, self.d).into()
+    }
+
     /// Attempts to convert the matrix to one which can be used in affine
     /// backgrounds.
     pub fn try_to_background(&self) -> Result<AffineMatrixBackground, OverflowError> {
@@ -177,6 +227,17 @@ impl AffineMatrix {
         })
     }
 
+    #[must_use]
+    /// Whether converting the matrix to one which can be used in affine
+    /// backgrounds would change it. Backgrounds store the coefficients with
+    /// the same 8 fractional bits as this, so no precision is lost from the
+    /// fractional part and this is only the case when a coefficient is too
+    /// large to fit. See [`AffineMatrixN::background_conversion_is_lossy`] for
+    /// matrices which are more precise than a background can be.
+    pub fn background_conversion_is_lossy(&self) -> bool {
+        self.to_background_wrapping().to_affine_matrix() != *self
+    }
+
     #[must_use]
     /// Converts the matrix to one which can be used in affine backgrounds
     /// wrapping any value which is too large to be represented there.
@@ -191,6 +252,36 @@ impl AffineMatrix {
         }
     }
 
+    #[must_use]
+    /// Converts the matrix to one which can be used in affine backgrounds
+    /// clamping any value which is too large to be represented there.
+    pub fn to_background_clamped(&self) -> AffineMatrixBackground {
+        AffineMatrixBackground {
+            a: saturating_change_base(self.a),
+            b: saturating_change_base(self.b),
+            c: saturating_change_base(self.c),
+            d: saturating_change_base(self.d),
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    /// Attempts to convert the matrix to one which can be used in affine
+    /// backgrounds. On overflow, the clamped matrix as given by
+    /// [`to_background_clamped`][AffineMatrix::to_background_clamped] is
+    /// returned alongside the error so the conversion doesn't need repeating.
+    pub fn try_to_background_or_clamped(
+        &self,
+    ) -> Result<AffineMatrixBackground, (OverflowError, AffineMatrixBackground)> {
+        let clamped = self.to_background_clamped();
+
+        if clamped.to_affine_matrix() == *self {
+            Ok(clamped)
+        } else {
+            Err((OverflowError(()), clamped))
+        }
+    }
+
     /// Attempts to convert the matrix to one which can be used in affine
     /// objects.
     pub fn try_to_object(&self) -> Result<AffineMatrixObject, OverflowError> {
@@ -202,6 +293,40 @@ impl AffineMatrix {
         })
     }
 
+    #[must_use]
+    /// Whether the matrix can be used for affine objects without visual
+    /// artifacts. This is stricter than [`try_to_object`][AffineMatrix::try_to_object]
+    /// which only checks that the coefficients fit in the object matrix.
+    ///
+    /// The hardware itself copes with any coefficient which fits, drawing the
+    /// parts of the object which sample from outside its sprite as
+    /// transparent. But once a coefficient has a magnitude of 64 or more,
+    /// neighbouring pixels on screen sample the sprite at least 64 pixels
+    /// apart, which is as wide as sprites get, so at most a pixel or two of it
+    /// shows and those flicker in and out as it moves. This limit is a
+    /// heuristic for keeping objects recognisable rather than a limit of the
+    /// hardware, and requires each of the coefficients to be strictly between
+    /// -64 and 64. The translation is not used by objects so is not checked.
+    pub fn fits_object_safely(&self) -> bool {
+        let limit = AffineMatrixElement::new(64);
+
+        [self.a, self.b, self.c, self.d]
+            .iter()
+            .all(|&coefficient| -limit < coefficient && coefficient < limit)
+    }
+
+    #[must_use]
+    /// Converts the matrix to one which can be used in affine objects
+    /// clamping any value which is too large to be represented there.
+    pub fn to_object_clamped(&self) -> AffineMatrixObject {
+        AffineMatrixObject {
+            a: saturating_change_base(self.a),
+            b: saturating_change_base(self.b),
+            c: saturating_change_base(self.c),
+            d: saturating_change_base(self.d),
+        }
+    }
+
     #[must_use]
     /// Converts the matrix to one which can be used in affine objects
     /// wrapping any value which is too large to be represented there.
@@ -214,10 +339,49 @@ impl AffineMatrix {
         }
     }
 
+    #[must_use]
+    /// Converts the matrix to one which can be used in affine objects, rounding
+    /// each coefficient to the nearest multiple of `step` and clamping it to
+    /// what can be represented. The hardware's own precision is 1/256, which
+    /// is what [`to_object_clamped`][AffineMatrix::to_object_clamped] gives,
+    /// but a coarser step makes something which is slowly scaling or rotating
+    /// look steadier by ignoring changes too small to do more than make a few
+    /// pixels flicker back and forth. Which step is small enough to still look
+    /// smooth depends on the size of the object, since a difference of `step`
+    /// in a coefficient moves the edge of an object `n` pixels across by
+    /// `step * n / 2` pixels in its sprite.
+    ///
+    /// Panics if `step` isn't positive.
+    pub fn quantize_for_object(&self, step: Num<i32, 8>) -> AffineMatrixObject {
+        let step = step.to_raw();
+        assert!(step > 0, "quantization step must be positive");
+
+        let max = i32::from(i16::MAX).div_euclid(step) * step;
+        let min = -((-i32::from(i16::MIN)).div_euclid(step) * step);
+        let quantize = |value: AffineMatrixElement| {
+            let rounded = (value.to_raw() + step / 2).div_euclid(step) * step;
+            Num::from_raw(rounded.clamp(min, max) as i16)
+        };
+
+        AffineMatrixObject {
+            a: quantize(self.a),
+            b: quantize(self.b),
+            c: quantize(self.c),
+            d: quantize(self.d),
+        }
+    }
+
     #[must_use]
     /// Creates an affine matrix from a given (x, y) scaling. This will scale by
-    /// the inverse, ie (2, 2) will produce half the size.
+    /// the inverse, ie (2, 2) will produce half the size. In debug builds this
+    /// panics if either component of the scale is too large to be sensible,
+    /// which usually means it overflowed while being calculated.
     pub fn from_scale(scale: Vector2D<Num<i32, 8>>) -> AffineMatrix {
+        debug_assert!(
+            is_sane(scale.x) && is_sane(scale.y),
+            "scale {scale:?} is too large, did it overflow?"
+        );
+
         AffineMatrix {
             a: scale.x,
             b: 0.into(),
@@ -227,6 +391,574 @@ impl AffineMatrix {
             y: 0.into(),
         }
     }
+
+    #[must_use]
+    /// Creates a scaling matrix as [`from_scale`][AffineMatrix::from_scale]
+    /// with the x scale multiplied by `aspect`, the ratio of the width to the
+    /// height of what should look square. A texture with an aspect of 2 is
+    /// stored twice as wide as it should appear, so this samples it twice as
+    /// quickly along x to keep circles round, including once rotated.
+    pub fn from_scale_aspect(scale: Vector2D<Num<i32, 8>>, aspect: Num<i32, 8>) -> AffineMatrix {
+        Self::from_scale((scale.x * aspect, scale.y).into())
+    }
+
+    #[must_use]
+    /// Creates an affine matrix which skews along the x axis, so a point is
+    /// moved along x by `amount` times its y coordinate. As with
+    /// [`from_scale`][AffineMatrix::from_scale] this is the inverse of what is
+    /// seen, a positive amount will lean the top of the texture to the right.
+    pub fn from_skew_x(amount: Num<i32, 8>) -> AffineMatrix {
+        AffineMatrix {
+            b: amount,
+            ..AffineMatrix::identity()
+        }
+    }
+
+    #[must_use]
+    /// Creates an affine matrix which skews along the y axis, so a point is
+    /// moved along y by `amount` times its x coordinate. This is the same as
+    /// [`from_skew_x`][AffineMatrix::from_skew_x] with the axes swapped.
+    pub fn from_skew_y(amount: Num<i32, 8>) -> AffineMatrix {
+        AffineMatrix {
+            c: amount,
+            ..AffineMatrix::identity()
+        }
+    }
+
+    #[must_use]
+    /// Creates an affine matrix which zooms in by a whole number `factor`, so
+    /// each pixel of the background or object is shown as a square `factor`
+    /// pixels across, for scaling up pixel art. Since
+    /// [`from_scale`][AffineMatrix::from_scale] takes the inverse of the scale,
+    /// this is the same as `from_scale` of `1 / factor`. For factors which
+    /// are powers of two this is exact, other factors are as close as the
+    /// precision of the matrix allows. For scales which aren't whole numbers,
+    /// use `from_scale` instead. Panics if `factor` isn't positive.
+    pub fn from_integer_scale(factor: i32) -> AffineMatrix {
+        assert!(factor > 0, "scale factor must be positive");
+
+        let scale = Num::new(1) / factor;
+        AffineMatrix::from_scale((scale, scale).into())
+    }
+
+    #[must_use]
+    /// Whether the matrix mirrors what it transforms, which is when the
+    /// determinant is negative. This can be used to detect unintended flips
+    /// when combining transformations.
+    pub fn is_reflected(&self) -> bool {
+        // the exact determinant, since even a tiny negative determinant reflects
+        self.raw_determinant() < 0
+    }
+
+    fn raw_determinant(&self) -> i64 {
+        i64::from(self.a.to_raw()) * i64::from(self.d.to_raw())
+            - i64::from(self.b.to_raw()) * i64::from(self.c.to_raw())
+    }
+
+    #[must_use]
+    /// The matrix which undoes this one, so that multiplying the two together
+    /// gives the [identity][AffineMatrix::identity] to within rounding.
+    /// Returns [None] if the determinant is zero, as then there is no inverse,
+    /// or if the inverse is too large to be represented.
+    pub fn inverse(&self) -> Option<AffineMatrix> {
+        // The determinant has 16 fractional bits, so dividing a product of two
+        // raw values by it leaves none, and one raw value by it leaves -8.
+        let determinant = i128::from(self.raw_determinant());
+        if determinant == 0 {
+            return None;
+        }
+
+        let [a, b, c, d, x, y] = self.to_array().map(|n| i128::from(n.to_raw()));
+
+        let element = |n: i128| -> Option<AffineMatrixElement> {
+            i32::try_from(n / determinant).ok().map(Num::from_raw)
+        };
+
+        Some(AffineMatrix {
+            a: element(d << 16)?,
+            b: element(-b << 16)?,
+            c: element(-c << 16)?,
+            d: element(a << 16)?,
+            x: element((b * y - d * x) << 8)?,
+            y: element((c * x - a * y) << 8)?,
+        })
+    }
+
+    #[must_use]
+    /// Finds the matrix which transforms each of the points in `src` to the
+    /// corresponding point in `dst`, so that `src[i]` maps to `dst[i]`.
+    /// Returns [None] if the points in `src` are collinear, as then there is
+    /// no unique solution, or if the result can't be represented.
+    ///
+    /// Remember that the GBA uses the matrix to go from screen space to texture
+    /// space, so to make the texture points `t` appear at the screen points `s`
+    /// you want `from_point_correspondence(s, t)`.
+    pub fn from_point_correspondence(
+        src: [Vector2D<Num<i32, 8>>; 3],
+        dst: [Vector2D<Num<i32, 8>>; 3],
+    ) -> Option<AffineMatrix> {
+        // Solve using the raw values in i64s to avoid overflowing. The product
+        // of two raw values has 16 fractional bits.
+        fn raw_difference(a: Vector2D<Num<i32, 8>>, b: Vector2D<Num<i32, 8>>) -> (i64, i64) {
+            (
+                i64::from(a.x.to_raw()) - i64::from(b.x.to_raw()),
+                i64::from(a.y.to_raw()) - i64::from(b.y.to_raw()),
+            )
+        }
+
+        let u1 = raw_difference(src[1], src[0]);
+        let u2 = raw_difference(src[2], src[0]);
+        let v1 = raw_difference(dst[1], dst[0]);
+        let v2 = raw_difference(dst[2], dst[0]);
+
+        let determinant = u1.0 * u2.1 - u2.0 * u1.1;
+        if determinant == 0 {
+            return None;
+        }
+
+        let coefficient = |n: i64| -> Option<AffineMatrixElement> {
+            Some(Num::from_raw(((n << 8) / determinant).try_into().ok()?))
+        };
+
+        let a = coefficient(v1.0 * u2.1 - v2.0 * u1.1)?;
+        let b = coefficient(v2.0 * u1.0 - v1.0 * u2.0)?;
+        let c = coefficient(v1.1 * u2.1 - v2.1 * u1.1)?;
+        let d = coefficient(v2.1 * u1.0 - v1.1 * u2.0)?;
+
+        Some(AffineMatrix {
+            a,
+            b,
+            c,
+            d,
+            x: dst[0].x - (a * src[0].x + b * src[0].y),
+            y: dst[0].y - (c * src[0].x + d * src[0].y),
+        })
+    }
+
+    #[must_use]
+    /// Multiplies two matrices, returning [None] if any of the intermediate
+    /// products or sums overflow. The result is otherwise identical to using
+    /// `*`, which will silently wrap on overflow.
+    pub fn checked_mul(&self, rhs: &AffineMatrix) -> Option<AffineMatrix> {
+        fn mul(a: AffineMatrixElement, b: AffineMatrixElement) -> Option<AffineMatrixElement> {
+            let product = (i64::from(a.to_raw()) * i64::from(b.to_raw())) >> 8;
+            Some(Num::from_raw(product.try_into().ok()?))
+        }
+
+        fn add(a: AffineMatrixElement, b: AffineMatrixElement) -> Option<AffineMatrixElement> {
+            a.to_raw().checked_add(b.to_raw()).map(Num::from_raw)
+        }
+
+        Some(AffineMatrix {
+            a: add(mul(self.a, rhs.a)?, mul(self.b, rhs.c)?)?,
+            b: add(mul(self.a, rhs.b)?, mul(self.b, rhs.d)?)?,
+            c: add(mul(self.c, rhs.a)?, mul(self.d, rhs.c)?)?,
+            d: add(mul(self.c, rhs.b)?, mul(self.d, rhs.d)?)?,
+            x: add(add(mul(self.a, rhs.x)?, mul(self.b, rhs.y)?)?, self.x)?,
+            y: add(add(mul(self.c, rhs.x)?, mul(self.d, rhs.y)?)?, self.y)?,
+        })
+    }
+
+    #[must_use]
+    /// Adjusts the translation of the matrix so that `point` on the screen maps
+    /// to the same `point` in the texture, keeping the rotation, scale and
+    /// shear. This makes `point` the pivot that the transform happens about,
+    /// so rotating a background around a visible point is simply
+    /// `AffineMatrix::from_rotation(angle).with_reference_point(point)`.
+    ///
+    /// This is the same as passing `point` as both the `transform_origin` and
+    /// the `position` to
+    /// [`from_scale_rotation_position`][AffineMatrixBackground::from_scale_rotation_position].
+    pub fn with_reference_point(self, point: Vector2D<Num<i32, 8>>) -> AffineMatrix {
+        AffineMatrix {
+            x: point.x - (self.a * point.x + self.b * point.y),
+            y: point.y - (self.c * point.x + self.d * point.y),
+            ..self
+        }
+    }
+
+    #[must_use]
+    /// The rotation of the matrix in revolutions, in the range [0, 1), in the
+    /// units that [`from_rotation`][AffineMatrix::from_rotation] takes. This is
+    /// the angle of the transformed x axis, so it only fully describes the
+    /// matrix if it consists of just rotations and uniform scales.
+    pub fn rotation(&self) -> Num<i32, 16> {
+        // Only the ratio between the two matters, so reinterpreting the raw
+        // values with more fractional bits gives more precision without any
+        // risk of overflow.
+        Num::<i32, 16>::from_raw(self.c.to_raw()).atan2(Num::from_raw(self.a.to_raw()))
+    }
+
+    #[must_use]
+    /// The rotational part of the matrix, with the scale and translation
+    /// removed by normalising each [axis][AffineMatrix::x_axis] to have a
+    /// length of 1. This is only exact for matrices made of rotations and
+    /// scales. A sheared matrix doesn't have axes at right angles, so what
+    /// this returns for one is neither a rotation nor meaningful. Panics if
+    /// either axis has zero length.
+    pub fn rotation_matrix(&self) -> AffineMatrix {
+        // Num::sqrt only keeps half of the fractional bits, which isn't precise
+        // enough for normalising, so this works on the raw values instead
+        let normalise = |axis: Vector2D<AffineMatrixElement>| {
+            let (x, y) = (i64::from(axis.x.to_raw()), i64::from(axis.y.to_raw()));
+            let length = (x * x + y * y).unsigned_abs().isqrt() as i64;
+            assert_ne!(length, 0, "axes of the matrix must have non zero length");
+
+            Vector2D::new(
+                Num::from_raw(((x << 8) / length) as i32),
+                Num::from_raw(((y << 8) / length) as i32),
+            )
+        };
+
+        let x_axis = normalise(self.x_axis());
+        let y_axis = normalise(self.y_axis());
+
+        AffineMatrix {
+            a: x_axis.x,
+            b: y_axis.x,
+            c: x_axis.y,
+            d: y_axis.y,
+            x: 0.into(),
+            y: 0.into(),
+        }
+    }
+
+    #[must_use]
+    /// Generates the matrix which converts between coordinates where y grows
+    /// upwards, with the origin at the bottom of a screen of the given height,
+    /// and screen coordinates where y grows downwards. A point at `y` above the
+    /// bottom ends up at row `screen_height - y`.
+    ///
+    /// Flipping is its own inverse, so this same matrix also converts screen
+    /// coordinates back to y up coordinates.
+    pub fn y_up_to_screen(screen_height: i32) -> AffineMatrix {
+        AffineMatrix {
+            a: 1.into(),
+            b: 0.into(),
+            c: 0.into(),
+            d: (-1).into(),
+            x: 0.into(),
+            y: screen_height.into(),
+        }
+    }
+
+    /// Rotates the linear part of the matrix by the small angle `delta`, given
+    /// in revolutions, leaving the translation unchanged. For a pure rotation
+    /// this is the same as adding `delta` to the angle passed to
+    /// [`from_rotation`][AffineMatrix::from_rotation].
+    ///
+    /// This avoids calculating `cos` and `sin` by approximating the rotation,
+    /// which is accurate for angles less than around 1/32 of a revolution, so
+    /// is useful for rotating a little every frame. Rounding errors build up
+    /// over many calls though, so occasionally call
+    /// [`orthonormalize`][AffineMatrix::orthonormalize] to correct the drift.
+    pub fn rotate_by<const N: usize>(&mut self, delta: Num<i32, N>) {
+        const ONE: i64 = 1 << 16;
+        const TAU: i64 = 411_775; // 2π with 16 fractional bits
+
+        let theta = i64::from(delta.change_base::<i32, 16>().to_raw()) * TAU / ONE;
+        let sin = theta;
+        let cos = ONE - theta * theta / (2 * ONE);
+
+        let rotate = |cos_part: AffineMatrixElement, sin_part: AffineMatrixElement| {
+            let raw = cos * i64::from(cos_part.to_raw()) + sin * i64::from(sin_part.to_raw());
+            AffineMatrixElement::from_raw(((raw + ONE / 2) >> 16) as i32)
+        };
+
+        *self = AffineMatrix {
+            a: rotate(self.a, -self.c),
+            b: rotate(self.b, -self.d),
+            c: rotate(self.c, self.a),
+            d: rotate(self.d, self.b),
+            x: self.x,
+            y: self.y,
+        };
+    }
+
+    /// Corrects a matrix which should be a pure rotation but has drifted, for
+    /// instance after many calls to [`rotate_by`][AffineMatrix::rotate_by], by
+    /// making the axes perpendicular and of length one again. This assumes the
+    /// matrix is already close to a rotation, and removes any scaling, skewing
+    /// or reflection. The translation is unchanged.
+    pub fn orthonormalize(&mut self) {
+        const ONE: i64 = 1 << 16;
+
+        // the closest rotation, in 16 fractional bits, but not yet of length one
+        let cos = i64::from(self.a.to_raw() + self.d.to_raw()) << 7;
+        let sin = i64::from(self.c.to_raw() - self.b.to_raw()) << 7;
+
+        // one step of newton's method for 1 / sqrt(length squared), which is
+        // accurate when the length is already close to one
+        let length_squared = (cos * cos + sin * sin) / ONE;
+        let scale = (3 * ONE - length_squared) / 2;
+
+        let to_element = |value: i64| {
+            AffineMatrixElement::from_raw(((((value * scale) >> 16) + (1 << 7)) >> 8) as i32)
+        };
+
+        self.a = to_element(cos);
+        self.b = to_element(-sin);
+        self.c = to_element(sin);
+        self.d = to_element(cos);
+    }
+
+    #[must_use]
+    /// Converts the values of the matrix to fixed point numbers with `N`
+    /// fractional bits, in the order a, b, c, d, x, y. This is for use with
+    /// code which works at a different precision. Returns [None] if any of the
+    /// values can't be represented with that many fractional bits.
+    pub fn to_precision<const N: usize>(&self) -> Option<[Num<i32, N>; 6]> {
+        let change_precision = |value: AffineMatrixElement| {
+            // done in an i64 since try_change_base doesn't detect the overflow
+            // when shifting up within the same integer type
+            let raw = i64::from(value.to_raw());
+            let raw = if N > 8 {
+                raw.checked_mul(1 << (N - 8))?
+            } else {
+                raw >> (8 - N)
+            };
+
+            Some(Num::from_raw(raw.try_into().ok()?))
+        };
+
+        Some([
+            change_precision(self.a)?,
+            change_precision(self.b)?,
+            change_precision(self.c)?,
+            change_precision(self.d)?,
+            change_precision(self.x)?,
+            change_precision(self.y)?,
+        ])
+    }
+
+    #[must_use]
+    /// The values of the matrix in the order a, b, c, d, x, y, for copying
+    /// into buffers or operating on each of them in a loop.
+    pub fn to_array(&self) -> [AffineMatrixElement; 6] {
+        [self.a, self.b, self.c, self.d, self.x, self.y]
+    }
+
+    #[must_use]
+    /// Creates the matrix from its values in the order a, b, c, d, x, y, the
+    /// reverse of [`to_array`][AffineMatrix::to_array].
+    pub fn from_array([a, b, c, d, x, y]: [AffineMatrixElement; 6]) -> Self {
+        AffineMatrix { a, b, c, d, x, y }
+    }
+
+    #[must_use]
+    /// The matrix as a 3x3 homogeneous matrix in row major order, so the
+    /// outer array is the rows, laid out as `[[a, b, x], [c, d, y], [0, 0, 1]]`.
+    /// This is the layout most maths libraries and tools use for 2D affine
+    /// transformations.
+    pub fn to_homogeneous(&self) -> [[AffineMatrixElement; 3]; 3] {
+        [
+            [self.a, self.b, self.x],
+            [self.c, self.d, self.y],
+            [0.into(), 0.into(), 1.into()],
+        ]
+    }
+
+    #[must_use]
+    /// Creates the matrix from a 3x3 homogeneous matrix in the layout of
+    /// [`to_homogeneous`][AffineMatrix::to_homogeneous], returning `None` if
+    /// the bottom row isn't `[0, 0, 1]` as then it isn't an affine matrix.
+    pub fn from_homogeneous(
+        [[a, b, x], [c, d, y], bottom]: [[AffineMatrixElement; 3]; 3],
+    ) -> Option<Self> {
+        (bottom == [0.into(), 0.into(), 1.into()]).then_some(AffineMatrix { a, b, c, d, x, y })
+    }
+
+    #[must_use]
+    /// Adds `offset` to the translation of the matrix, which is the same as
+    /// `AffineMatrix::from_translation(-offset) * self` but without performing
+    /// a full matrix multiplication. This is a cheap way of placing an
+    /// already rotated or scaled matrix.
+    pub fn then_translate(self, offset: Vector2D<Num<i32, 8>>) -> AffineMatrix {
+        AffineMatrix {
+            x: self.x + offset.x,
+            y: self.y + offset.y,
+            ..self
+        }
+    }
+
+    #[must_use]
+    /// The matrix as three vectors, `(origin, x_basis, y_basis)`. The origin is
+    /// what (0, 0) is transformed to, and the bases are the
+    /// [`x_axis`][AffineMatrix::x_axis] and [`y_axis`][AffineMatrix::y_axis].
+    /// This is the inverse of [`from_basis`][AffineMatrix::from_basis].
+    pub fn to_basis(
+        &self,
+    ) -> (
+        Vector2D<Num<i32, 8>>,
+        Vector2D<Num<i32, 8>>,
+        Vector2D<Num<i32, 8>>,
+    ) {
+        ((self.x, self.y).into(), self.x_axis(), self.y_axis())
+    }
+
+    #[must_use]
+    /// Creates the matrix which transforms (0, 0) to `origin`, and steps of
+    /// one pixel along the x and y axes to `x_basis` and `y_basis`. This is
+    /// the inverse of [`to_basis`][AffineMatrix::to_basis].
+    pub fn from_basis(
+        origin: Vector2D<Num<i32, 8>>,
+        x_basis: Vector2D<Num<i32, 8>>,
+        y_basis: Vector2D<Num<i32, 8>>,
+    ) -> AffineMatrix {
+        AffineMatrix {
+            a: x_basis.x,
+            b: y_basis.x,
+            c: x_basis.y,
+            d: y_basis.y,
+            x: origin.x,
+            y: origin.y,
+        }
+    }
+
+    #[must_use]
+    /// Generates the matrix that represents a rotation by a whole number of
+    /// degrees, in the same direction as
+    /// [`from_rotation`][AffineMatrix::from_rotation]. This looks the sine and
+    /// cosine up in a table generated at build time rather than calculating
+    /// them, so is faster and gives exactly the same result every time. The
+    /// table has an entry for each degree, so costs 720 bytes of ROM.
+    pub fn from_rotation_deg_lut(deg: u16) -> AffineMatrix {
+        static SIN_DEGREES: [i16; 360] = include!(concat!(env!("OUT_DIR"), "/sin_degrees.rs"));
+
+        let sin_of =
+            |deg: u16| AffineMatrixElement::from_raw(SIN_DEGREES[usize::from(deg % 360)].into());
+        let sin = sin_of(deg);
+        let cos = sin_of(deg % 360 + 90);
+
+        AffineMatrix {
+            a: cos,
+            b: -sin,
+            c: sin,
+            d: cos,
+            x: 0.into(),
+            y: 0.into(),
+        }
+    }
+
+    #[must_use]
+    /// Linearly interpolates each component of the matrix between `self` and
+    /// `other`, where a `t` of 0 gives `self` and a `t` of 1 gives `other`.
+    /// Interpolating between two rotations doesn't keep the scale constant,
+    /// so this is best suited to matrices which are fairly close together.
+    pub fn interpolate(self, other: AffineMatrix, t: Num<i32, 8>) -> AffineMatrix {
+        let lerp = |from: AffineMatrixElement, to: AffineMatrixElement| from + (to - from) * t;
+
+        AffineMatrix {
+            a: lerp(self.a, other.a),
+            b: lerp(self.b, other.b),
+            c: lerp(self.c, other.c),
+            d: lerp(self.d, other.d),
+            x: lerp(self.x, other.x),
+            y: lerp(self.y, other.y),
+        }
+    }
+
+    #[must_use]
+    /// Returns this matrix followed by a rotation, ie
+    /// `AffineMatrix::from_rotation(angle) * self`. Each method in a chain like
+    /// `AffineMatrix::identity().rotated(a).translated(v)` is applied after the
+    /// ones to its left, so this rotates and then translates.
+    pub fn rotated<const N: usize>(self, angle: Num<i32, N>) -> Self {
+        AffineMatrix::from_rotation(angle) * self
+    }
+
+    #[must_use]
+    /// Returns this matrix followed by a scale, ie
+    /// `AffineMatrix::from_scale(scale) * self`. See [`rotated`][Self::rotated]
+    /// for how chains are ordered.
+    pub fn scaled(self, scale: Vector2D<Num<i32, 8>>) -> Self {
+        AffineMatrix::from_scale(scale) * self
+    }
+
+    #[must_use]
+    /// Returns this matrix followed by a translation, ie
+    /// `AffineMatrix::from_translation(position) * self`. See
+    /// [`rotated`][Self::rotated] for how chains are ordered.
+    pub fn translated(self, position: Vector2D<Num<i32, 8>>) -> Self {
+        AffineMatrix::from_translation(position) * self
+    }
+
+    #[must_use]
+    /// The corners of an object which is `half_size` from its centre to its
+    /// bottom right corner in each direction, after being transformed by this
+    /// matrix. They are relative to the centre of the object on screen, going
+    /// clockwise from the top left. The matrix maps the screen onto the
+    /// sprite, so this maps the corners back the other way. Returns [None] if
+    /// the matrix isn't invertible, such as when its scale is zero, as then
+    /// there is no way back.
+    pub fn sprite_corners(
+        &self,
+        half_size: Vector2D<Num<i32, 8>>,
+    ) -> Option<[Vector2D<Num<i32, 8>>; 4]> {
+        // the exact determinant, as rounding it would make small scales lose
+        // all their precision or even seem not to be invertible
+        let determinant = i128::from(self.raw_determinant());
+        if determinant == 0 {
+            return None;
+        }
+
+        let [a, b, c, d] = [self.a, self.b, self.c, self.d].map(|n| i128::from(n.to_raw()));
+        // products of two raw values have 16 fractional bits like the
+        // determinant, so shifting by 8 first leaves 8 after dividing
+        let component = |n: i128| Num::from_raw(((n << 8) / determinant) as i32);
+
+        Some([(-1, -1), (1, -1), (1, 1), (-1, 1)].map(|(x, y)| {
+            let corner = (
+                i128::from(half_size.x.to_raw() * x),
+                i128::from(half_size.y.to_raw() * y),
+            );
+            Vector2D::new(
+                component(d * corner.0 - b * corner.1),
+                component(a * corner.1 - c * corner.0),
+            )
+        }))
+    }
+
+    #[must_use]
+    /// The distance from the centre of an object which is `half_size` from
+    /// its centre to its bottom right corner in each direction to the
+    /// furthest of its [corners][AffineMatrix::sprite_corners] once
+    /// transformed, for culling it against a circle. Returns [None] if the
+    /// matrix isn't invertible, like [`sprite_corners`][AffineMatrix::sprite_corners].
+    pub fn transformed_radius(&self, half_size: Vector2D<Num<i32, 8>>) -> Option<Num<i32, 8>> {
+        self.sprite_corners(half_size)?
+            .into_iter()
+            .map(Vector2D::magnitude)
+            .max()
+    }
+
+    #[must_use]
+    /// Generates the matrix which shows the texture with its origin at `from`
+    /// and its positive x axis pointing towards `to`, for pointing something
+    /// like a turret at its target. If the two points are the same there is
+    /// no direction to face, so the texture isn't rotated.
+    pub fn from_look_at(from: Vector2D<Num<i32, 8>>, to: Vector2D<Num<i32, 8>>) -> AffineMatrix {
+        let direction = to - from;
+        // as in rotation, more fractional bits gives a more precise angle
+        let angle = Num::<i32, 16>::from_raw(direction.y.to_raw())
+            .atan2(Num::from_raw(direction.x.to_raw()));
+
+        // the matrix maps from the screen to the texture, so it needs to undo
+        // the rotation and translation rather than do them
+        AffineMatrix::from_rotation(-angle) * AffineMatrix::from_translation(from)
+    }
+
+    #[must_use]
+    /// Converts between the matrix the GBA uses, which maps from the screen
+    /// to the texture, and the conventional one which maps from the texture,
+    /// or model, to the screen. The two are the [inverse][AffineMatrix::inverse]
+    /// of each other, so this same conversion also goes back from the
+    /// conventional matrix to the GBA's. Returns [None] when
+    /// [`inverse`][AffineMatrix::inverse] does, such as for a matrix which
+    /// shrinks everything down to nothing.
+    pub fn to_forward_convention(&self) -> Option<AffineMatrix> {
+        self.inverse()
+    }
 }
 
 impl Default for AffineMatrix {
@@ -235,6 +967,14 @@ impl Default for AffineMatrix {
     }
 }
 
+// Num doesn't implement Hash, so this hashes the raw values instead, which are
+// equal exactly when the matrices are.
+impl Hash for AffineMatrix {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_array().map(Num::to_raw).hash(state);
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(C, packed(4))]
 /// An affine matrix that can be used in affine backgrounds
@@ -247,9 +987,36 @@ pub struct AffineMatrixBackground {
     y: Num<i32, 8>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The backgrounds which can be affine, each of which has its own set of
+/// affine registers.
+pub enum BackgroundLayer {
+    /// Background 2, whose registers are from `0x0400_0020` to `0x0400_002F`
+    Bg2 = 2,
+    /// Background 3, whose registers are from `0x0400_0030` to `0x0400_003F`
+    Bg3 = 3,
+}
+
+impl BackgroundLayer {
+    fn register_address(self) -> usize {
+        0x0400_0000 + 0x10 * self as usize
+    }
+
+    fn committed(self) -> &'static Static<AffineMatrixBackground> {
+        &COMMITTED_BACKGROUND_MATRICES[self as usize - 2]
+    }
+}
+
+// The affine registers are write only, so this keeps a copy of what was last
+// written to them for reading back
+static COMMITTED_BACKGROUND_MATRICES: [Static<AffineMatrixBackground>; 2] = [
+    Static::new(AffineMatrixBackground::IDENTITY),
+    Static::new(AffineMatrixBackground::IDENTITY),
+];
+
 impl Default for AffineMatrixBackground {
     fn default() -> Self {
-        AffineMatrix::identity().to_background_wrapping()
+        Self::IDENTITY
     }
 }
 
@@ -262,6 +1029,11 @@ impl TryFrom<AffineMatrix> for AffineMatrixBackground {
 }
 
 impl AffineMatrixBackground {
+    /// The identity matrix, which leaves the background untransformed. Unlike
+    /// [`AffineMatrix::identity`] this can be used in constants and statics.
+    pub const IDENTITY: AffineMatrixBackground =
+        AffineMatrixBackground::from_raw(1 << 8, 0, 0, 1 << 8, 0, 0);
+
     #[must_use]
     /// Converts to the affine matrix that is usable in performing efficient
     /// calculations.
@@ -303,20 +1075,232 @@ impl AffineMatrixBackground {
         rotation: Num<i32, 16>,
         position: Vector2D<Num<i32, 8>>,
     ) -> Self {
-        crate::syscall::bg_affine_matrix(
+        let (transform_origin, position, scale, rotation) =
+            Self::bg_affine_syscall_args(transform_origin, position, scale, rotation);
+
+        crate::syscall::bg_affine_matrix(transform_origin, position, scale, rotation)
+    }
+
+    #[must_use]
+    /// The same as [`from_scale_rotation_position`][AffineMatrixBackground::from_scale_rotation_position]
+    /// but rounds the position to the nearest pixel rather than flooring it.
+    /// Flooring is biased by half a pixel, so rounding keeps something moving
+    /// slowly by sub pixel amounts closer to where it should be.
+    pub fn from_scale_rotation_position_rounded(
+        transform_origin: Vector2D<Num<i32, 8>>,
+        scale: Vector2D<Num<i32, 8>>,
+        rotation: Num<i32, 16>,
+        position: Vector2D<Num<i32, 8>>,
+    ) -> Self {
+        let half = Num::new(1) / 2;
+        let rounded = (position + (half, half).into()).floor();
+
+        Self::from_scale_rotation_position(
+            transform_origin,
+            scale,
+            rotation,
+            Vector2D::new(rounded.x.into(), rounded.y.into()),
+        )
+    }
+
+    #[must_use]
+    /// Creates a background transformation which rotates by `rotation` and
+    /// scales by `scale` about the centre of the screen, so the background
+    /// pixel at (120, 80) stays in the middle of the screen. This assumes the
+    /// centre of the screen is the point of interest of the background, so for
+    /// any other pivot use [`from_scale_rotation_position`][AffineMatrixBackground::from_scale_rotation_position]
+    /// instead.
+    pub fn rotating_about_screen_center(
+        rotation: Num<i32, 16>,
+        scale: Vector2D<Num<i32, 8>>,
+    ) -> Self {
+        let center: Vector2D<Num<i32, 8>> = (WIDTH / 2, HEIGHT / 2).into();
+        Self::from_scale_rotation_position(center, scale, rotation, center)
+    }
+
+    #[must_use]
+    /// The arguments which [`from_scale_rotation_position`][AffineMatrixBackground::from_scale_rotation_position]
+    /// passes to the `BgAffineSet` syscall, in the order and types which the
+    /// syscall expects them. The position is floored to a whole pixel and the
+    /// rotation is wrapped to within a single turn. Panics if the position or
+    /// scale don't fit in an `i16`.
+    pub fn bg_affine_syscall_args(
+        transform_origin: Vector2D<Num<i32, 8>>,
+        position: Vector2D<Num<i32, 8>>,
+        scale: Vector2D<Num<i32, 8>>,
+        rotation: Num<i32, 16>,
+    ) -> (
+        Vector2D<Num<i32, 8>>,
+        Vector2D<i16>,
+        Vector2D<Num<i16, 8>>,
+        Num<u16, 16>,
+    ) {
+        (
             transform_origin,
             position.try_change_base::<i16, 8>().unwrap().floor(),
             scale.try_change_base().unwrap(),
             rotation.rem_euclid(1.into()).try_change_base().unwrap(),
         )
     }
-}
 
-impl From<AffineMatrixBackground> for AffineMatrix {
-    fn from(mat: AffineMatrixBackground) -> Self {
-        mat.to_affine_matrix()
-    }
-}
+    #[must_use]
+    /// The same as [`from_scale_rotation_position`][AffineMatrixBackground::from_scale_rotation_position]
+    /// but returns the result as an [AffineMatrix], for when it needs to be
+    /// combined with further transformations.
+    pub fn from_scale_rotation_position_matrix(
+        transform_origin: Vector2D<Num<i32, 8>>,
+        scale: Vector2D<Num<i32, 8>>,
+        rotation: Num<i32, 16>,
+        position: Vector2D<Num<i32, 8>>,
+    ) -> AffineMatrix {
+        Self::from_scale_rotation_position(transform_origin, scale, rotation, position)
+            .to_affine_matrix()
+    }
+
+    // The struct is packed, so references to its fields may be unaligned. These
+    // copy the fields out instead so users never need to form references.
+
+    #[must_use]
+    #[inline]
+    /// The `a` coefficient, written to the PA register
+    pub fn a(&self) -> Num<i16, 8> {
+        self.a
+    }
+
+    #[must_use]
+    #[inline]
+    /// The `b` coefficient, written to the PB register
+    pub fn b(&self) -> Num<i16, 8> {
+        self.b
+    }
+
+    #[must_use]
+    #[inline]
+    /// The `c` coefficient, written to the PC register
+    pub fn c(&self) -> Num<i16, 8> {
+        self.c
+    }
+
+    #[must_use]
+    #[inline]
+    /// The `d` coefficient, written to the PD register
+    pub fn d(&self) -> Num<i16, 8> {
+        self.d
+    }
+
+    #[must_use]
+    #[inline]
+    /// The x coordinate of the translation, written to the X register
+    pub fn x(&self) -> Num<i32, 8> {
+        self.x
+    }
+
+    #[must_use]
+    #[inline]
+    /// The y coordinate of the translation, written to the Y register
+    pub fn y(&self) -> Num<i32, 8> {
+        self.y
+    }
+
+    #[must_use]
+    /// Returns whether this matrix differs from `previous`. The comparison is
+    /// exact on the values that get written to the background registers, so
+    /// this can be used to skip writing a transform which hasn't changed since
+    /// the last frame.
+    pub fn changed_since(&self, previous: &AffineMatrixBackground) -> bool {
+        self != previous
+    }
+
+    /// Writes the matrix to the affine registers of the background `layer`.
+    /// This is applied from the next scanline drawn, so can be used during the
+    /// horizontal blank to change the transformation part way down the screen.
+    pub fn commit_to_bg(&self, layer: BackgroundLayer) {
+        let registers: MemoryMapped<AffineMatrixBackground> =
+            unsafe { MemoryMapped::new(layer.register_address()) };
+        registers.set(*self);
+        layer.committed().write(*self);
+    }
+
+    #[must_use]
+    /// The matrix which background 2 is currently being transformed by. The
+    /// affine registers are write only, so reading them directly doesn't give
+    /// back what was written. Instead this is the matrix most recently
+    /// [committed][AffineMatrixBackground::commit_to_bg] to it, or the
+    /// identity if nothing has been, and doesn't include anything written to
+    /// the registers some other way, such as by DMA.
+    pub fn read_from_bg2() -> AffineMatrixBackground {
+        BackgroundLayer::Bg2.committed().read()
+    }
+
+    #[must_use]
+    /// The matrix which background 3 is currently being transformed by, with
+    /// the same limitations as [`read_from_bg2`][AffineMatrixBackground::read_from_bg2].
+    pub fn read_from_bg3() -> AffineMatrixBackground {
+        BackgroundLayer::Bg3.committed().read()
+    }
+
+    #[must_use]
+    /// Linearly interpolates each value between `self` and `other`, where a
+    /// `t` of 0 gives `self` and a `t` of 1 gives `other`. This works on the
+    /// values directly, so is cheap enough to do for every scanline, for
+    /// example to blend between two transformations down the screen.
+    pub fn lerp(&self, other: &AffineMatrixBackground, t: Num<i32, 8>) -> AffineMatrixBackground {
+        let t = t.to_raw();
+        let lerp_coefficient = |from: Num<i16, 8>, to: Num<i16, 8>| {
+            let (from, to) = (i32::from(from.to_raw()), i32::from(to.to_raw()));
+            Num::from_raw((from + (((to - from) * t) >> 8)) as i16)
+        };
+        let lerp_position = |from: Num<i32, 8>, to: Num<i32, 8>| {
+            // positions can be far enough apart for this to overflow an i32
+            let (from, to) = (i64::from(from.to_raw()), i64::from(to.to_raw()));
+            Num::from_raw((from + (((to - from) * i64::from(t)) >> 8)) as i32)
+        };
+
+        AffineMatrixBackground {
+            a: lerp_coefficient(self.a, other.a),
+            b: lerp_coefficient(self.b, other.b),
+            c: lerp_coefficient(self.c, other.c),
+            d: lerp_coefficient(self.d, other.d),
+            x: lerp_position(self.x, other.x),
+            y: lerp_position(self.y, other.y),
+        }
+    }
+
+    #[must_use]
+    /// Creates the matrix from the raw values of the background's affine
+    /// registers, in the order PA, PB, PC, PD, X, Y. The values are interpreted
+    /// as fixed point numbers with 8 fractional bits.
+    pub const fn from_raw(a: i16, b: i16, c: i16, d: i16, x: i32, y: i32) -> Self {
+        AffineMatrixBackground {
+            a: Num::from_raw(a),
+            b: Num::from_raw(b),
+            c: Num::from_raw(c),
+            d: Num::from_raw(d),
+            x: Num::from_raw(x),
+            y: Num::from_raw(y),
+        }
+    }
+
+    #[must_use]
+    /// The raw values as they would be written to the background's affine
+    /// registers, in the same order as [`from_raw`][AffineMatrixBackground::from_raw].
+    pub fn to_raw(&self) -> (i16, i16, i16, i16, i32, i32) {
+        (
+            self.a.to_raw(),
+            self.b.to_raw(),
+            self.c.to_raw(),
+            self.d.to_raw(),
+            self.x.to_raw(),
+            self.y.to_raw(),
+        )
+    }
+}
+
+impl From<AffineMatrixBackground> for AffineMatrix {
+    fn from(mat: AffineMatrixBackground) -> Self {
+        mat.to_affine_matrix()
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(C, packed(4))]
@@ -356,6 +1340,51 @@ impl AffineMatrixObject {
             y: 0.into(),
         }
     }
+
+    #[must_use]
+    /// Converts to the affine matrix that is usable in performing efficient
+    /// calculations, with the translation set such that its
+    /// [`position`][AffineMatrix::position] is `position`. Objects don't use
+    /// the translation, but this is useful when rendering the same
+    /// transformation in software.
+    pub fn to_affine_matrix_with_position(&self, position: Vector2D<Num<i32, 8>>) -> AffineMatrix {
+        AffineMatrix {
+            x: -position.x,
+            y: -position.y,
+            ..self.to_affine_matrix()
+        }
+    }
+
+    #[must_use]
+    /// The raw values of `a`, `b`, `c` and `d` in that order as little endian
+    /// bytes, for example to send the transformation over a link cable. This
+    /// is exactly reversed by [`from_le_bytes`][AffineMatrixObject::from_le_bytes].
+    pub fn to_le_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0; 8];
+        for (chunk, value) in bytes
+            .chunks_exact_mut(2)
+            .zip([self.a, self.b, self.c, self.d])
+        {
+            chunk.copy_from_slice(&value.to_raw().to_le_bytes());
+        }
+        bytes
+    }
+
+    #[must_use]
+    /// Creates the matrix from bytes as produced by
+    /// [`to_le_bytes`][AffineMatrixObject::to_le_bytes].
+    pub fn from_le_bytes(bytes: [u8; 8]) -> Self {
+        let value = |index: usize| {
+            Num::from_raw(i16::from_le_bytes([bytes[index * 2], bytes[index * 2 + 1]]))
+        };
+
+        AffineMatrixObject {
+            a: value(0),
+            b: value(1),
+            c: value(2),
+            d: value(3),
+        }
+    }
 }
 
 impl From<AffineMatrixObject> for AffineMatrix {
@@ -364,17 +1393,509 @@ impl From<AffineMatrixObject> for AffineMatrix {
     }
 }
 
+/// Objects have no translation in their affine matrix, so this only keeps the
+/// rotation, scale and skew of the background's matrix.
+impl From<AffineMatrixBackground> for AffineMatrixObject {
+    fn from(mat: AffineMatrixBackground) -> Self {
+        AffineMatrixObject {
+            a: mat.a,
+            b: mat.b,
+            c: mat.c,
+            d: mat.d,
+        }
+    }
+}
+
+const OBJECT_AFFINE_MEMORY: MemoryMapped1DArray<u16, 512> =
+    unsafe { MemoryMapped1DArray::new(0x0700_0000) };
+
+/// The number of affine matrices which objects can share between them
+pub const AFFINE_SLOT_COUNT: usize = 32;
+
+#[derive(Debug, PartialEq, Eq)]
+/// One of the [AFFINE_SLOT_COUNT] places in object attribute memory which an
+/// [AffineMatrixObject] can be written to, as handed out by an
+/// [AffineSlotAllocator]. This can't be copied or outlive the
+/// [ObjectController] it came from, so only one holder can write to the slot
+/// and it can't be freed twice.
+pub struct AffineSlot<'a>(u8, PhantomData<&'a ObjectController>);
+
+impl AffineSlot<'_> {
+    #[must_use]
+    /// The index of the slot, which is what affine objects refer to it by
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The error emitted when allocating from an [AffineSlotAllocator] which has
+/// no free slots left
+pub struct AffineSlotsExhausted(pub(crate) ());
+
+/// Hands out the affine slots of object attribute memory so that objects
+/// don't overwrite each other's matrices. These are created by
+/// [ObjectController::affine_slots] and all take from the controller's one
+/// set of slots, so two allocators never hand out the same slot.
+pub struct AffineSlotAllocator<'a> {
+    controller: &'a ObjectController,
+}
+
+impl<'a> AffineSlotAllocator<'a> {
+    pub(crate) fn new(controller: &'a ObjectController) -> Self {
+        Self { controller }
+    }
+
+    /// Takes a free slot, or returns an error if all of them are in use.
+    pub fn allocate(&mut self) -> Result<AffineSlot<'a>, AffineSlotsExhausted> {
+        self.controller
+            .take_affine_slot()
+            .map(|index| AffineSlot(index, PhantomData))
+            .ok_or(AffineSlotsExhausted(()))
+    }
+
+    /// Returns the slot to the [ObjectController] so it can be handed out again
+    pub fn free(&mut self, slot: AffineSlot<'a>) {
+        self.controller.return_affine_slot(slot.0);
+    }
+
+    /// Writes the matrix to the slot in object attribute memory
+    pub fn write(&mut self, slot: &AffineSlot<'a>, matrix: AffineMatrixObject) {
+        // the matrices are spread through the unused fourth attribute of
+        // every object, so consecutive values are 4 halfwords apart
+        let base = usize::from(slot.0) * 16 + 3;
+        for (i, value) in [matrix.a, matrix.b, matrix.c, matrix.d]
+            .into_iter()
+            .enumerate()
+        {
+            OBJECT_AFFINE_MEMORY.set(base + i * 4, value.to_raw() as u16);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The error emitted when pushing onto an [AffineMatrixStack] which is full
+pub struct StackFullError(pub(crate) ());
+
+#[derive(Debug, Clone)]
+/// A fixed capacity stack of transformations which can be used to build up
+/// hierarchies of transforms, like a scene graph, without allocating.
+///
+/// Pushing a matrix combines it with the current transform such that the
+/// pushed matrix is performed first, so something pushed after its parent is
+/// transformed relative to that parent. Popping returns to the transform as it
+/// was before the push.
+pub struct AffineMatrixStack<const N: usize> {
+    matrices: [AffineMatrix; N],
+    len: usize,
+}
+
+impl<const N: usize> AffineMatrixStack<N> {
+    #[must_use]
+    /// Creates an empty stack, the current transform of which is the identity
+    pub fn new() -> Self {
+        AffineMatrixStack {
+            matrices: [AffineMatrix::identity(); N],
+            len: 0,
+        }
+    }
+
+    /// Pushes `matrix` onto the stack, making the current transform `matrix`
+    /// followed by the previous current transform. Fails if the stack already
+    /// contains `N` matrices.
+    pub fn push(&mut self, matrix: AffineMatrix) -> Result<(), StackFullError> {
+        if self.len == N {
+            return Err(StackFullError(()));
+        }
+
+        self.matrices[self.len] = matrix * self.current();
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Removes the most recently pushed transform, returning the current
+    /// transform as it was before popping. Returns [None] if the stack is empty.
+    pub fn pop(&mut self) -> Option<AffineMatrix> {
+        let current = self.current();
+        self.len = self.len.checked_sub(1)?;
+        Some(current)
+    }
+
+    #[must_use]
+    /// The combination of all the transforms on the stack
+    pub fn current(&self) -> AffineMatrix {
+        match self.len {
+            0 => AffineMatrix::identity(),
+            len => self.matrices[len - 1],
+        }
+    }
+}
+
+impl<const N: usize> Default for AffineMatrixStack<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[must_use]
+/// Converts a value of an [AffineMatrix] to the 16 bit representation used by
+/// the hardware, clamping it to the nearest value which can be represented
+/// rather than wrapping around. This is how the clamped conversions, such as
+/// [`AffineMatrix::to_background_clamped`], convert each value.
+pub fn saturating_change_base(value: Num<i32, 8>) -> Num<i16, 8> {
+    Num::from_raw(value.to_raw().clamp(i16::MIN.into(), i16::MAX.into()) as i16)
+}
+
+/// The largest magnitude of the inputs to the constructors which is checked in
+/// debug builds. Nothing legitimate gets close to this, so anything beyond it
+/// is most likely garbage from an earlier overflow.
+const SANE_LIMIT: u32 = 1 << 15;
+
+fn is_sane<const N: usize>(value: Num<i32, N>) -> bool {
+    value.floor().unsigned_abs() <= SANE_LIMIT
+}
+
+#[must_use]
+/// Wraps an angle, given in revolutions, into the range [0, 1) in the same way
+/// as [`AffineMatrix::from_rotation`]. This is useful for keeping an angle which
+/// is accumulated over time, like the direction something is spinning or
+/// aiming, from growing without bound.
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::affine::wrap_angle, fixnum::num};
+/// # fn foo() {
+/// assert_eq!(wrap_angle::<8>(num!(1.25)), num!(0.25));
+/// assert_eq!(wrap_angle::<8>(num!(-0.25)), num!(0.75));
+/// # }
+/// ```
+pub fn wrap_angle<const N: usize>(angle: Num<i32, N>) -> Num<i32, N> {
+    angle.rem_euclid(1.into())
+}
+
+/// Samples a texture at a fractional position by blending the four texels
+/// surrounding it, weighted by how close `pos` is to each of them. The texels
+/// are read through `get` as 15 bit BGR colours and each channel is blended
+/// separately. This gives a smoother result than picking the nearest texel
+/// when reading back a background through an affine transformation.
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::affine::bilinear_sample, fixnum::{num, Vector2D}};
+/// # fn foo() {
+/// let texture = [[0x001f, 0x03e0], [0x7c00, 0x7fff]];
+/// let get = |x: i32, y: i32| texture[y.clamp(0, 1) as usize][x.clamp(0, 1) as usize];
+///
+/// let colour = bilinear_sample(get, Vector2D::new(num!(0.25), num!(0.75)));
+/// # }
+/// ```
+pub fn bilinear_sample(get: impl Fn(i32, i32) -> u16, pos: Vector2D<Num<i32, 8>>) -> u16 {
+    let (x, y) = (pos.x.floor(), pos.y.floor());
+    let (fx, fy) = (pos.x.frac() as u32, pos.y.frac() as u32);
+
+    let weights = [
+        (256 - fx) * (256 - fy),
+        fx * (256 - fy),
+        (256 - fx) * fy,
+        fx * fy,
+    ];
+    let texels = [get(x, y), get(x + 1, y), get(x, y + 1), get(x + 1, y + 1)];
+
+    let channel = |shift: u16| {
+        let total: u32 = texels
+            .iter()
+            .zip(weights)
+            .map(|(&texel, weight)| u32::from((texel >> shift) & 0x1f) * weight)
+            .sum();
+
+        (((total + (1 << 15)) >> 16) as u16) << shift
+    };
+
+    channel(0) | channel(5) | channel(10)
+}
+
+/// Generates one background transformation per screen row which together
+/// project the background onto a floor stretching away to the horizon, the
+/// classic "mode 7" effect. These are intended to be written to the affine
+/// registers of the background during each horizontal blank, for instance
+/// using HBlank DMA.
+///
+/// The camera sits `camera_height` pixels above the point `camera_position` of
+/// the background, looking towards negative y when `rotation` is zero and
+/// turning clockwise as `rotation` increases. The screen row at `horizon` is
+/// infinitely far away, so raising or lowering the horizon tilts the camera.
+/// The horizontal field of view is a quarter turn.
+///
+/// Rows on or above the horizon show no floor, so they are given the identity
+/// transformation. These would usually be hidden using a window or covered by
+/// another background.
+pub fn mode7_scanlines(
+    horizon: i32,
+    camera_position: Vector2D<Num<i32, 8>>,
+    camera_height: Num<i32, 8>,
+    rotation: Num<i32, 8>,
+) -> impl Iterator<Item = AffineMatrixBackground> {
+    let cos = rotation.cos();
+    let sin = rotation.sin();
+
+    (0..HEIGHT).map(move |row| {
+        if row <= horizon {
+            return AffineMatrixBackground::default();
+        }
+
+        // the size of a screen pixel on this row when projected onto the floor
+        let scale = camera_height / (row - horizon);
+        let half_width = scale * (WIDTH / 2);
+
+        // the left edge of the screen on this row, found by moving forward to
+        // the row and then left by half of its width
+        AffineMatrix {
+            a: scale * cos,
+            b: 0.into(),
+            c: scale * sin,
+            d: 0.into(),
+            x: camera_position.x + half_width * (sin - cos),
+            y: camera_position.y - half_width * (cos + sin),
+        }
+        .to_background_clamped()
+    })
+}
+
+/// Transforms a background by a different matrix on every row of the screen,
+/// such as those from [mode7_scanlines], by using HBlank DMA to write each
+/// row's matrix to the background's affine registers just before it is drawn.
+///
+/// This uses DMA channel 0, which copies the 16 bytes of the next row's matrix
+/// at the start of each horizontal blank, pausing the CPU while it does. There
+/// are no horizontal blank transfers during the vertical blank, and the DMA
+/// moves through the matrices as it goes, so [`start_frame`][ScanlineAffineDma::start_frame]
+/// needs to be called during every vertical blank to start again from the
+/// top. Anything else using DMA channel 0 stops this from working, and the
+/// transfers stop when this is dropped.
+pub struct ScanlineAffineDma {
+    layer: BackgroundLayer,
+    // one for each row, plus one more for the transfer during the horizontal
+    // blank of the last row
+    matrices: Vec<AffineMatrixBackground>,
+}
+
+impl ScanlineAffineDma {
+    const WORDS_PER_MATRIX: u16 = (core::mem::size_of::<AffineMatrixBackground>() / 4) as u16;
+
+    #[must_use]
+    /// Creates the transfers for background `layer` with a matrix for each row
+    /// of the screen in order from the top. Nothing happens until the first
+    /// call to [`start_frame`][ScanlineAffineDma::start_frame]. Panics if there
+    /// isn't exactly one matrix for each row.
+    pub fn new(
+        layer: BackgroundLayer,
+        matrices: impl IntoIterator<Item = AffineMatrixBackground>,
+    ) -> Self {
+        let mut matrices: Vec<_> = matrices.into_iter().collect();
+        assert_eq!(
+            matrices.len(),
+            HEIGHT as usize,
+            "there must be a matrix for each row of the screen"
+        );
+        matrices.push(matrices[0]);
+
+        ScanlineAffineDma { layer, matrices }
+    }
+
+    /// The matrix for each row of the screen, which can be changed between
+    /// frames. Changes made while the screen is being drawn take effect part
+    /// way down it.
+    pub fn matrices_mut(&mut self) -> &mut [AffineMatrixBackground] {
+        &mut self.matrices[..HEIGHT as usize]
+    }
+
+    /// Commits the matrix for the top row and then starts the transfers for
+    /// the rest of the rows. This must be called during each vertical blank.
+    pub fn start_frame(&self) {
+        self.matrices[0].commit_to_bg(self.layer);
+        unsafe {
+            dma::hblank_dma0_copy32(
+                self.matrices[1..].as_ptr().cast(),
+                self.layer.register_address() as *mut u32,
+                Self::WORDS_PER_MATRIX,
+                true,
+            );
+        }
+    }
+}
+
+impl Drop for ScanlineAffineDma {
+    fn drop(&mut self) {
+        dma::stop_dma0();
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl crate::test_runner::ApproxEq for AffineMatrix {
+    type Epsilon = AffineMatrixElement;
+
+    fn approx_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        [
+            (self.a, other.a),
+            (self.b, other.b),
+            (self.c, other.c),
+            (self.d, other.d),
+            (self.x, other.x),
+            (self.y, other.y),
+        ]
+        .iter()
+        .all(|(a, b)| a.approx_eq(b, epsilon))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Rotation matrices precomputed for `N` evenly spaced angles, for when many
+/// things rotate to a few different angles. Looking up the nearest of the
+/// cached matrices is much cheaper than calculating the rotation each time.
+///
+/// The angles are quantized to steps of `1 / N` of a turn, so the matrix given
+/// for an angle is out by up to half of that step.
+pub struct AngleMatrixCache<const N: usize> {
+    matrices: [AffineMatrix; N],
+}
+
+impl<const N: usize> AngleMatrixCache<N> {
+    #[must_use]
+    /// Calculates the rotation matrices for the `N` angles. Panics if `N` is 0.
+    pub fn new() -> Self {
+        assert!(N > 0, "cache must have at least one angle");
+
+        AngleMatrixCache {
+            matrices: core::array::from_fn(|i| {
+                AffineMatrix::from_rotation(Num::<i32, 16>::new(i as i32) / N as i32)
+            }),
+        }
+    }
+
+    #[must_use]
+    /// The cached rotation matrix for the angle nearest to `angle`, which is
+    /// in revolutions like [`AffineMatrix::from_rotation`].
+    pub fn get<const M: usize>(&self, angle: Num<i32, M>) -> AffineMatrix {
+        let angle = i64::from(wrap_angle(angle).to_raw());
+        let nearest = (angle * N as i64 + (1 << M >> 1)) >> M;
+
+        self.matrices[nearest as usize % N]
+    }
+}
+
+impl<const N: usize> Default for AngleMatrixCache<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[must_use]
+/// Combines the transformation of a camera, such as from
+/// [`Camera2D::view_matrix`], with a viewport which positions the camera's
+/// view in the rectangle `viewport` of the screen rather than across the whole
+/// screen. The result is a single transformation from the screen to the world
+/// which can be used for a background.
+///
+/// As with all the transformations here, this maps from the screen to the
+/// world, so it is `camera * viewport` where `viewport` maps screen pixels in
+/// the rectangle to where they would be on a full screen view. The viewport is
+/// applied to a point first, and then the camera. Panics if the viewport has no
+/// area.
+pub fn world_to_screen(camera: &AffineMatrix, viewport: Rect<i32>) -> AffineMatrix {
+    assert!(
+        viewport.size.x > 0 && viewport.size.y > 0,
+        "viewport must have a positive size"
+    );
+
+    let scale = (
+        Num::new(WIDTH) / viewport.size.x,
+        Num::new(HEIGHT) / viewport.size.y,
+    );
+    let viewport = AffineMatrix::from_scale(scale.into())
+        * AffineMatrix::from_translation(viewport.position.change_base());
+
+    *camera * viewport
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A camera looking down on a background, as in a top down game, which can
+/// follow a target around a larger world while rotating and zooming.
+pub struct Camera2D {
+    /// The point of the background shown in the centre of the screen
+    pub position: Vector2D<Num<i32, 8>>,
+    /// The rotation of the view in revolutions, applied in the same way as
+    /// [`AffineMatrix::from_rotation`]
+    pub rotation: Num<i32, 8>,
+    /// How many screen pixels each background pixel covers, so values greater
+    /// than one zoom in
+    pub zoom: Num<i32, 8>,
+}
+
+impl Camera2D {
+    #[must_use]
+    /// Creates a camera centred on `position` with no rotation or zoom
+    pub fn new(position: Vector2D<Num<i32, 8>>) -> Self {
+        Camera2D {
+            position,
+            rotation: 0.into(),
+            zoom: 1.into(),
+        }
+    }
+
+    #[must_use]
+    /// The transformation for a background which shows the view of the
+    /// camera, with the camera's position at the centre of the screen.
+    pub fn view_matrix(&self) -> AffineMatrix {
+        let inverse_zoom = Num::new(1) / self.zoom;
+        let linear = AffineMatrix::from_rotation(self.rotation)
+            * AffineMatrix::from_scale((inverse_zoom, inverse_zoom).into());
+
+        let centre: Vector2D<Num<i32, 8>> = (WIDTH / 2, HEIGHT / 2).into();
+
+        AffineMatrix {
+            x: self.position.x - (linear.a * centre.x + linear.b * centre.y),
+            y: self.position.y - (linear.c * centre.x + linear.d * centre.y),
+            ..linear
+        }
+    }
+}
+
 impl Mul for AffineMatrix {
     type Output = Self;
     fn mul(self, rhs: Self) -> Self::Output {
-        AffineMatrix {
-            a: self.a * rhs.a + self.b * rhs.c,
-            b: self.a * rhs.b + self.b * rhs.d,
-            c: self.c * rhs.a + self.d * rhs.c,
-            d: self.c * rhs.b + self.d * rhs.d,
-            x: self.a * rhs.x + self.b * rhs.y + self.x,
-            y: self.c * rhs.x + self.d * rhs.y + self.y,
-        }
+        multiply(self, rhs)
+    }
+}
+
+/// Multiplication of [Num]s splits each number into its integer and
+/// fractional parts to avoid overflow, which takes four multiplies. In ARM
+/// mode the full 64 bit product of two registers is a single instruction, so
+/// this does the multiplication that way from IWRAM instead, rounding the same
+/// way so that the result is identical.
+///
+/// `test_multiply_is_faster_than_reference` times both versions with the
+/// hardware timers and fails if this one isn't faster.
+#[cfg_attr(not(doc), instruction_set(arm::a32))]
+#[link_section = ".iwram.affine_multiply"]
+fn multiply(lhs: AffineMatrix, rhs: AffineMatrix) -> AffineMatrix {
+    let product = |lhs: AffineMatrixElement, rhs: AffineMatrixElement| {
+        ((i64::from(lhs.to_raw()) * i64::from(rhs.to_raw())) >> 8) as i32
+    };
+
+    AffineMatrix {
+        a: Num::from_raw(product(lhs.a, rhs.a) + product(lhs.b, rhs.c)),
+        b: Num::from_raw(product(lhs.a, rhs.b) + product(lhs.b, rhs.d)),
+        c: Num::from_raw(product(lhs.c, rhs.a) + product(lhs.d, rhs.c)),
+        d: Num::from_raw(product(lhs.c, rhs.b) + product(lhs.d, rhs.d)),
+        x: Num::from_raw(product(lhs.a, rhs.x) + product(lhs.b, rhs.y) + lhs.x.to_raw()),
+        y: Num::from_raw(product(lhs.c, rhs.x) + product(lhs.d, rhs.y) + lhs.y.to_raw()),
+    }
+}
+
+impl Product for AffineMatrix {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(AffineMatrix::identity(), |acc, matrix| acc * matrix)
     }
 }
 
@@ -384,28 +1905,1798 @@ impl MulAssign for AffineMatrix {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::fixnum::num;
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// An affine matrix with `FRAC` fractional bits, for building up
+/// transformations with more precision than [`AffineMatrix`] before narrowing
+/// to it. With 16 fractional bits, rotations by a small fraction of a turn are
+/// no longer rounded away, at the cost of only being able to represent values
+/// up to ±32768.
+pub struct AffineMatrixN<const FRAC: usize> {
+    a: Num<i32, FRAC>,
+    b: Num<i32, FRAC>,
+    c: Num<i32, FRAC>,
+    d: Num<i32, FRAC>,
+    x: Num<i32, FRAC>,
+    y: Num<i32, FRAC>,
+}
 
-    use super::*;
+impl<const FRAC: usize> AffineMatrixN<FRAC> {
+    #[must_use]
+    /// The identity matrix
+    pub fn identity() -> Self {
+        AffineMatrixN {
+            a: 1.into(),
+            b: 0.into(),
+            c: 0.into(),
+            d: 1.into(),
+            x: 0.into(),
+            y: 0.into(),
+        }
+    }
 
-    #[test_case]
-    fn test_simple_multiply(_: &mut crate::Gba) {
-        let position = (20, 10).into();
+    #[must_use]
+    /// Generates the matrix that represents a rotation, calculated with `FRAC`
+    /// fractional bits. See [`AffineMatrix::from_rotation`].
+    pub fn from_rotation<const N: usize>(angle: Num<i32, N>) -> Self {
+        let angle: Num<i32, FRAC> = wrap_angle(angle).change_base();
+        let cos = wide_cos(angle);
+        let sin = wide_cos(angle - Num::from_raw(1 << FRAC) / 4);
 
-        let a = AffineMatrix::from_translation(position);
-        let b = AffineMatrix::default();
+        AffineMatrixN {
+            a: cos,
+            b: -sin,
+            c: sin,
+            d: cos,
+            x: 0.into(),
+            y: 0.into(),
+        }
+    }
 
-        let c = a * b;
+    #[must_use]
+    /// Narrows the matrix to an [`AffineMatrix`], truncating any precision
+    /// beyond its 8 fractional bits
+    pub fn to_affine_matrix(self) -> AffineMatrix {
+        AffineMatrix {
+            a: self.a.change_base(),
+            b: self.b.change_base(),
+            c: self.c.change_base(),
+            d: self.d.change_base(),
+            x: self.x.change_base(),
+            y: self.y.change_base(),
+        }
+    }
 
-        assert_eq!(c.position(), position);
+    #[must_use]
+    /// Whether converting the matrix to one which can be used in affine
+    /// backgrounds would change it, either because a coefficient has
+    /// precision beyond the 8 fractional bits a background stores, which would
+    /// be truncated, or because it is too large to fit.
+    pub fn background_conversion_is_lossy(&self) -> bool {
+        let truncated = FRAC > 8
+            && [self.a, self.b, self.c, self.d, self.x, self.y]
+                .iter()
+                .any(|n| n.to_raw() & ((1 << (FRAC - 8)) - 1) != 0);
 
-        let d = AffineMatrix::from_rotation::<2>(num!(0.5));
+        truncated || self.to_affine_matrix().background_conversion_is_lossy()
+    }
+}
 
-        let e = a * d;
+impl<const FRAC: usize> Default for AffineMatrixN<FRAC> {
+    fn default() -> Self {
+        AffineMatrixN::identity()
+    }
+}
+
+impl<const FRAC: usize> From<AffineMatrix> for AffineMatrixN<FRAC> {
+    fn from(matrix: AffineMatrix) -> Self {
+        AffineMatrixN {
+            a: matrix.a.change_base(),
+            b: matrix.b.change_base(),
+            c: matrix.c.change_base(),
+            d: matrix.d.change_base(),
+            x: matrix.x.change_base(),
+            y: matrix.y.change_base(),
+        }
+    }
+}
+
+impl<const FRAC: usize> From<AffineMatrixN<FRAC>> for AffineMatrix {
+    fn from(matrix: AffineMatrixN<FRAC>) -> Self {
+        matrix.to_affine_matrix()
+    }
+}
+
+impl<const FRAC: usize> Mul for AffineMatrixN<FRAC> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mul = wide_mul::<FRAC>;
+
+        AffineMatrixN {
+            a: mul(self.a, rhs.a) + mul(self.b, rhs.c),
+            b: mul(self.a, rhs.b) + mul(self.b, rhs.d),
+            c: mul(self.c, rhs.a) + mul(self.d, rhs.c),
+            d: mul(self.c, rhs.b) + mul(self.d, rhs.d),
+            x: mul(self.a, rhs.x) + mul(self.b, rhs.y) + self.x,
+            y: mul(self.c, rhs.x) + mul(self.d, rhs.y) + self.y,
+        }
+    }
+}
+
+// Multiplying the fractional parts of two [Num]s overflows an i32 once there
+// are 16 fractional bits, so these work in i64s instead.
+fn wide_mul<const FRAC: usize>(lhs: Num<i32, FRAC>, rhs: Num<i32, FRAC>) -> Num<i32, FRAC> {
+    Num::from_raw(((i64::from(lhs.to_raw()) * i64::from(rhs.to_raw())) >> FRAC) as i32)
+}
+
+// The same approximation as [Num::cos], so that rotations agree with
+// [AffineMatrix::from_rotation] once narrowed
+fn wide_cos<const FRAC: usize>(angle: Num<i32, FRAC>) -> Num<i32, FRAC> {
+    let one = 1 << FRAC;
+
+    let mut x = i64::from(angle.to_raw());
+    x -= one / 4 + (((x + one / 4) >> FRAC) << FRAC);
+    x = (x * (x.abs() - one / 2) * 16) >> FRAC;
+
+    Num::from_raw(x as i32)
+}
+
+impl<const FRAC: usize> MulAssign for AffineMatrixN<FRAC> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+/// Yields `frames` matrices [interpolating][AffineMatrix::interpolate] evenly
+/// from `start` to `end`, the first being `start` and the last being `end`, so
+/// that a game loop can take the next one each frame. A single frame yields
+/// just `end`.
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// use agb::{display::affine::{animate, AffineMatrix}, fixnum::num};
+///
+/// # fn foo() {
+/// let spin = animate(
+///     AffineMatrix::identity(),
+///     AffineMatrix::from_rotation::<8>(num!(0.125)),
+///     30,
+/// );
+///
+/// for matrix in spin {
+///     // use the matrix, then wait for the next frame
+/// }
+/// # }
+/// ```
+pub fn animate(
+    start: AffineMatrix,
+    end: AffineMatrix,
+    frames: usize,
+) -> impl Iterator<Item = AffineMatrix> {
+    let steps = frames.saturating_sub(1) as i32;
+
+    (0..frames as i32).map(move |frame| {
+        if frame == steps {
+            end
+        } else {
+            start.interpolate(end, Num::new(frame) / steps)
+        }
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+/// A sequence of transformations at given frames, for animating something
+/// along a scripted path. Sampling between two keyframes
+/// [interpolates][AffineMatrix::interpolate] between them.
+pub struct TransformTimeline {
+    keyframes: Vec<(u32, AffineMatrix)>,
+}
+
+impl TransformTimeline {
+    #[must_use]
+    /// Creates a timeline with no keyframes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a keyframe with the transformation `matrix` at `frame`, replacing
+    /// any keyframe which is already at that frame.
+    pub fn add_keyframe(&mut self, frame: u32, matrix: AffineMatrix) {
+        match self
+            .keyframes
+            .binary_search_by_key(&frame, |&(frame, _)| frame)
+        {
+            Ok(index) => self.keyframes[index].1 = matrix,
+            Err(index) => self.keyframes.insert(index, (frame, matrix)),
+        }
+    }
+
+    #[must_use]
+    /// The transformation at `frame`. Before the first keyframe or after the
+    /// last this is the first or last keyframe respectively, and a timeline
+    /// without any keyframes is always the identity.
+    pub fn sample(&self, frame: u32) -> AffineMatrix {
+        let index = match self
+            .keyframes
+            .binary_search_by_key(&frame, |&(frame, _)| frame)
+        {
+            Ok(index) => return self.keyframes[index].1,
+            Err(index) => index,
+        };
+
+        match (
+            index.checked_sub(1).map(|index| self.keyframes[index]),
+            self.keyframes.get(index),
+        ) {
+            (Some((start, from)), Some(&(end, to))) => {
+                let t = Num::new((frame - start) as i32) / (end - start) as i32;
+                from.interpolate(to, t)
+            }
+            (Some((_, matrix)), None) | (None, Some(&(_, matrix))) => matrix,
+            (None, None) => AffineMatrix::identity(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assert_approx_eq, fixnum::num};
+
+    use super::*;
+
+    #[crate::test_case(max_cycles = 20_000)]
+    fn test_simple_multiply(_: &mut crate::Gba) {
+        let epsilon = num!(0.01);
+        let position = (20, 10).into();
+
+        let a = AffineMatrix::from_translation(position);
+        let b = AffineMatrix::default();
+
+        let c = a * b;
+
+        assert_approx_eq!(c.position(), position, epsilon);
+
+        let d = AffineMatrix::from_rotation::<2>(num!(0.5));
+
+        let e = a * d;
+
+        assert_approx_eq!(e.position(), position, epsilon);
+        assert_approx_eq!(d * d, AffineMatrix::identity(), epsilon);
+    }
+
+    #[test_case]
+    fn test_changed_since(_: &mut crate::Gba) {
+        let identity = AffineMatrixBackground::default();
+        let translated = AffineMatrix::from_translation((1, 0).into()).to_background_wrapping();
+
+        assert!(!identity.changed_since(&identity));
+        assert!(translated.changed_since(&identity));
+    }
+
+    #[test_case]
+    fn test_checked_mul(_: &mut crate::Gba) {
+        let a = AffineMatrix::from_translation((20, 10).into());
+        let b = AffineMatrix::from_rotation::<2>(num!(0.25));
+
+        assert_eq!(a.checked_mul(&b), Some(a * b));
+
+        let huge = AffineMatrix::from_scale((30_000, 30_000).into());
+        assert_eq!(huge.checked_mul(&huge), None);
+    }
+
+    #[test_case]
+    fn test_from_point_correspondence(_: &mut crate::Gba) {
+        let unit_triangle = [(0, 0).into(), (1, 0).into(), (0, 1).into()];
+        // the unit triangle rotated by a quarter turn and moved to (10, 10)
+        let moved_triangle = [(10, 10).into(), (10, 11).into(), (9, 10).into()];
+
+        assert_eq!(
+            AffineMatrix::from_point_correspondence(unit_triangle, moved_triangle),
+            Some(AffineMatrix {
+                a: 0.into(),
+                b: (-1).into(),
+                c: 1.into(),
+                d: 0.into(),
+                x: 10.into(),
+                y: 10.into(),
+            })
+        );
+
+        let collinear = [(0, 0).into(), (1, 1).into(), (2, 2).into()];
+        assert_eq!(
+            AffineMatrix::from_point_correspondence(collinear, moved_triangle),
+            None
+        );
+    }
+
+    #[test_case]
+    fn test_try_to_background_or_clamped(_: &mut crate::Gba) {
+        let small = AffineMatrix::from_scale((2, 3).into());
+        assert_eq!(
+            small.try_to_background_or_clamped(),
+            Ok(small.try_to_background().unwrap())
+        );
+
+        let large = AffineMatrix::from_scale((200, -200).into());
+        let (_, clamped) = large.try_to_background_or_clamped().unwrap_err();
+        assert_eq!(
+            clamped.to_affine_matrix(),
+            AffineMatrix::from_scale(
+                (
+                    Num::from_raw(i16::MAX.into()),
+                    Num::from_raw(i16::MIN.into())
+                )
+                    .into()
+            )
+        );
+    }
+
+    fn transform_point(
+        matrix: &AffineMatrix,
+        point: Vector2D<Num<i32, 8>>,
+    ) -> Vector2D<Num<i32, 8>> {
+        (
+            matrix.a * point.x + matrix.b * point.y + matrix.x,
+            matrix.c * point.x + matrix.d * point.y + matrix.y,
+        )
+            .into()
+    }
+
+    #[test_case]
+    fn test_with_reference_point(_: &mut crate::Gba) {
+        let pivot: Vector2D<Num<i32, 8>> = (120, 80).into();
+        let matrix = (AffineMatrix::from_rotation::<8>(num!(0.125))
+            * AffineMatrix::from_scale((2, 2).into()))
+        .with_reference_point(pivot);
+
+        assert_eq!(transform_point(&matrix, pivot), pivot);
+        assert_ne!(transform_point(&matrix, (0, 0).into()), (0, 0).into());
+    }
+
+    #[test_case]
+    fn test_matrix_stack_push_pop(_: &mut crate::Gba) {
+        let parent = AffineMatrix::from_translation((20, 10).into());
+        let child = AffineMatrix::from_rotation::<8>(num!(0.25));
+
+        let mut stack = AffineMatrixStack::<2>::new();
+        assert_eq!(stack.current(), AffineMatrix::identity());
+
+        stack.push(parent).unwrap();
+        stack.push(child).unwrap();
+        assert_eq!(stack.current(), child * parent);
+        assert_eq!(stack.push(child), Err(StackFullError(())));
+
+        assert_eq!(stack.pop(), Some(child * parent));
+        assert_eq!(stack.current(), parent);
+        assert_eq!(stack.pop(), Some(parent));
+        assert_eq!(stack.current(), AffineMatrix::identity());
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test_case]
+    fn test_rotation_round_trip(_: &mut crate::Gba) {
+        for angle in [
+            num!(0.),
+            num!(0.1),
+            num!(0.3),
+            num!(0.5),
+            num!(0.65),
+            num!(0.9),
+        ] {
+            let angle: Num<i32, 16> = angle;
+            let rotation = AffineMatrix::from_rotation(angle).rotation();
+
+            assert!(
+                (rotation - angle).abs() < num!(0.01),
+                "expected {angle}, got {rotation}"
+            );
+        }
+    }
+
+    #[test_case]
+    fn test_background_raw_round_trip(_: &mut crate::Gba) {
+        let raw = (256, -12, 40, 300, -5000, 123_456);
+        let matrix = AffineMatrixBackground::from_raw(raw.0, raw.1, raw.2, raw.3, raw.4, raw.5);
+
+        assert_eq!(matrix.to_raw(), raw);
+        assert_eq!(
+            AffineMatrixBackground::from_raw(256, 0, 0, 256, 0, 0),
+            AffineMatrixBackground::default()
+        );
+    }
+
+    #[test_case]
+    fn test_y_up_to_screen(_: &mut crate::Gba) {
+        let matrix = AffineMatrix::y_up_to_screen(160);
+
+        assert_eq!(transform_point(&matrix, (10, 0).into()), (10, 160).into());
+        assert_eq!(transform_point(&matrix, (10, 150).into()), (10, 10).into());
+        assert_eq!(matrix * matrix, AffineMatrix::identity());
+    }
+
+    #[test_case]
+    fn test_try_from_translation(_: &mut crate::Gba) {
+        let position = (500_000, -500_000).into();
+        assert_eq!(
+            AffineMatrix::try_from_translation(position),
+            Ok(AffineMatrix::from_translation(position))
+        );
+
+        assert_eq!(
+            AffineMatrix::try_from_translation((524_288, 0).into()),
+            Ok(AffineMatrix::from_translation((524_288, 0).into()))
+        );
+        assert_eq!(
+            AffineMatrix::try_from_translation((0, -524_288).into()),
+            Err(OverflowError(()))
+        );
+        assert_eq!(
+            AffineMatrix::try_from_translation((600_000, 0).into()),
+            Err(OverflowError(()))
+        );
+    }
+
+    #[test_case]
+    fn test_product(_: &mut crate::Gba) {
+        let matrices = [
+            AffineMatrix::from_translation((-5, -5).into()),
+            AffineMatrix::from_rotation::<8>(num!(0.125)),
+            AffineMatrix::from_scale((2, 3).into()),
+            AffineMatrix::from_translation((100, 50).into()),
+        ];
+
+        assert_eq!(
+            matrices.iter().copied().product::<AffineMatrix>(),
+            matrices[0] * matrices[1] * matrices[2] * matrices[3]
+        );
+        assert_eq!(
+            core::iter::empty().product::<AffineMatrix>(),
+            AffineMatrix::identity()
+        );
+    }
+
+    #[test_case]
+    fn test_mode7_scanlines(_: &mut crate::Gba) {
+        let camera_position: Vector2D<Num<i32, 8>> = (100, 200).into();
+        let horizon = 40;
+        let scanlines = || mode7_scanlines(horizon, camera_position, 32.into(), 0.into());
+
+        assert_eq!(scanlines().count(), HEIGHT as usize);
+
+        let mut previous_distance = None;
+        for (row, matrix) in (0..).zip(scanlines()) {
+            if row <= horizon {
+                assert_eq!(matrix, AffineMatrixBackground::default());
+                continue;
+            }
+
+            let centre = transform_point(&matrix.into(), (WIDTH / 2, row).into());
+
+            // looking straight ahead, the centre of the screen is directly in
+            // front of the camera
+            assert_eq!(centre.x, camera_position.x);
+            assert!(centre.y <= camera_position.y);
+
+            // and rows further down the screen are closer to the camera
+            let distance = camera_position.y - centre.y;
+            if let Some(previous_distance) = previous_distance {
+                assert!(distance <= previous_distance);
+            }
+            previous_distance = Some(distance);
+        }
+    }
+
+    #[test_case]
+    fn test_fits_object_safely(_: &mut crate::Gba) {
+        assert!(AffineMatrix::identity().fits_object_safely());
+        assert!(AffineMatrix::from_scale((num!(63.5), num!(-63.5)).into()).fits_object_safely());
+
+        // fits in the object matrix, but shows artifacts on hardware
+        let shrunk = AffineMatrix::from_scale((100, 1).into());
+        assert!(shrunk.try_to_object().is_ok());
+        assert!(!shrunk.fits_object_safely());
+
+        assert!(!AffineMatrix::from_scale((1, -64).into()).fits_object_safely());
+    }
+
+    #[test_case]
+    fn test_rotate_by(_: &mut crate::Gba) {
+        fn assert_close(actual: AffineMatrix, expected: AffineMatrix) {
+            let epsilon = num!(0.02);
+            for (actual, expected) in [
+                (actual.a, expected.a),
+                (actual.b, expected.b),
+                (actual.c, expected.c),
+                (actual.d, expected.d),
+            ] {
+                assert!(
+                    (actual - expected).abs() <= epsilon,
+                    "{actual:?} != {expected:?}"
+                );
+            }
+        }
+
+        let position = (20, 10).into();
+        let mut matrix = AffineMatrix::from_translation(position);
+
+        for _ in 0..16 {
+            matrix.rotate_by::<8>(num!(0.015625));
+        }
+
+        assert_close(matrix, AffineMatrix::from_rotation::<8>(num!(0.25)));
+        assert_eq!(matrix.position(), position, "translation is unchanged");
+
+        let rotation = |cos, sin: Num<i32, 8>| AffineMatrix {
+            a: cos,
+            b: -sin,
+            c: sin,
+            d: cos,
+            x: 0.into(),
+            y: 0.into(),
+        };
+
+        // a 3, 4, 5 triangle scaled up by 5% and skewed slightly
+        let mut drifted = rotation(num!(0.63), num!(0.84));
+        drifted.b += num!(0.02);
+        drifted.orthonormalize();
+        assert_close(drifted, rotation(num!(0.6), num!(0.8)));
+    }
+
+    #[test_case]
+    fn test_from_scale_rotation_position_matrix(_: &mut crate::Gba) {
+        let transform_origin = (120, 80).into();
+        let scale = (num!(1.5), num!(2.)).into();
+        let rotation = num!(0.125);
+        let position = (10, 20).into();
+
+        let background = AffineMatrixBackground::from_scale_rotation_position(
+            transform_origin,
+            scale,
+            rotation,
+            position,
+        );
+        let matrix = AffineMatrixBackground::from_scale_rotation_position_matrix(
+            transform_origin,
+            scale,
+            rotation,
+            position,
+        );
+
+        assert_eq!(matrix, background.to_affine_matrix());
+        assert_eq!(matrix.to_background_wrapping(), background);
+    }
+
+    #[test_case]
+    fn test_background_to_object(_: &mut crate::Gba) {
+        let background = AffineMatrixBackground::from_raw(1, 2, 3, 4, 5, 6);
+        let object = AffineMatrixObject::from(background);
+
+        assert_eq!(
+            object.to_affine_matrix(),
+            AffineMatrix {
+                x: 0.into(),
+                y: 0.into(),
+                ..background.to_affine_matrix()
+            }
+        );
+    }
+
+    #[test_case]
+    fn test_wrap_angle(_: &mut crate::Gba) {
+        assert_eq!(wrap_angle::<8>(num!(0.5)), num!(0.5));
+        assert_eq!(wrap_angle::<8>(num!(1.)), num!(0.));
+        assert_eq!(wrap_angle::<8>(num!(3.25)), num!(0.25));
+        assert_eq!(wrap_angle::<16>(num!(-0.25)), num!(0.75));
+
+        assert_eq!(
+            AffineMatrix::from_rotation::<8>(num!(2.125)),
+            AffineMatrix::from_rotation(wrap_angle::<8>(num!(2.125)))
+        );
+    }
+
+    #[test_case]
+    fn test_axes(_: &mut crate::Gba) {
+        let identity = AffineMatrix::identity();
+        assert_eq!(identity.x_axis(), (1, 0).into());
+        assert_eq!(identity.y_axis(), (0, 1).into());
+
+        let scaled = AffineMatrix::from_scale((2, 3).into());
+        assert_eq!(scaled.x_axis(), (2, 0).into());
+        assert_eq!(scaled.y_axis(), (0, 3).into());
+    }
+
+    #[test_case]
+    fn test_camera_2d(_: &mut crate::Gba) {
+        let target = (500, 300).into();
+        let centre = (WIDTH / 2, HEIGHT / 2).into();
+
+        let mut camera = Camera2D::new(target);
+        assert_eq!(transform_point(&camera.view_matrix(), centre), target);
+        assert_eq!(
+            transform_point(&camera.view_matrix(), (0, 0).into()),
+            target - centre
+        );
+
+        camera.rotation = num!(0.25);
+        camera.zoom = 2.into();
+        assert_eq!(transform_point(&camera.view_matrix(), centre), target);
+        assert_approx_eq!(
+            transform_point(&camera.view_matrix(), centre + (0, 2).into()),
+            target + (-1, 0).into(),
+            num!(0.01)
+        );
+    }
+
+    #[test_case]
+    fn test_to_precision(_: &mut crate::Gba) {
+        let matrix = AffineMatrix {
+            a: num!(1.5),
+            b: num!(-0.25),
+            c: num!(0.00390625),
+            d: num!(-2.),
+            x: num!(100.75),
+            y: num!(-20.5),
+        };
+
+        let precise = matrix.to_precision::<16>().unwrap();
+        assert_eq!(precise[0], num!(1.5));
+        assert_eq!(precise[2], num!(0.00390625));
+        assert_eq!(
+            precise.map(|value| value.change_base::<i32, 8>()),
+            [matrix.a, matrix.b, matrix.c, matrix.d, matrix.x, matrix.y]
+        );
+
+        let coarse = matrix.to_precision::<4>().unwrap();
+        for (coarse, original) in coarse
+            .iter()
+            .zip([matrix.a, matrix.b, matrix.c, matrix.d, matrix.x, matrix.y])
+        {
+            assert_approx_eq!(coarse.change_base::<i32, 8>(), original, num!(0.0625));
+        }
+
+        let far_away = AffineMatrix::from_translation((40_000, 0).into());
+        assert_eq!(far_away.to_precision::<16>(), None);
+    }
+
+    #[test_case]
+    fn test_background_identity(_: &mut crate::Gba) {
+        static LAYERS: [AffineMatrixBackground; 2] = [AffineMatrixBackground::IDENTITY; 2];
+
+        assert_eq!(
+            AffineMatrixBackground::IDENTITY,
+            AffineMatrix::identity().to_background_wrapping()
+        );
+        assert_eq!(
+            AffineMatrixBackground::IDENTITY,
+            AffineMatrixBackground::default()
+        );
+        assert_eq!(LAYERS[1], AffineMatrixBackground::default());
+    }
+
+    #[test_case]
+    fn test_then_translate(_: &mut crate::Gba) {
+        let offset = (num!(12.5), num!(-7.)).into();
+        let matrix = AffineMatrix::from_rotation::<8>(num!(0.125))
+            * AffineMatrix::from_scale((2, 3).into())
+            * AffineMatrix::from_translation((4, 5).into());
+
+        assert_eq!(
+            matrix.then_translate(offset),
+            AffineMatrix::from_translation(-offset) * matrix
+        );
+    }
+
+    #[test_case]
+    fn test_object_to_affine_matrix_with_position(_: &mut crate::Gba) {
+        let object = AffineMatrix::from_scale((2, 3).into()).to_object_wrapping();
+        let position = (30, 40).into();
+
+        let matrix = object.to_affine_matrix_with_position(position);
+
+        assert_eq!(matrix.x_axis(), object.to_affine_matrix().x_axis());
+        assert_eq!(matrix.y_axis(), object.to_affine_matrix().y_axis());
+        assert_eq!(matrix.position(), position);
+        assert_eq!(
+            matrix,
+            AffineMatrix::from_scale((2, 3).into()).then_translate(-position)
+        );
+    }
+
+    #[test_case]
+    fn test_is_reflected(_: &mut crate::Gba) {
+        assert!(AffineMatrix::from_scale((-1, 1).into()).is_reflected());
+        assert!(AffineMatrix::from_scale((1, -1).into()).is_reflected());
+        assert!(!AffineMatrix::from_scale((-1, -1).into()).is_reflected());
+        assert!(!AffineMatrix::identity().is_reflected());
+        assert!(!AffineMatrix::from_rotation::<8>(num!(0.375)).is_reflected());
+
+        let tiny = AffineMatrix::from_scale((num!(0.0625), num!(-0.0625)).into());
+        assert!(tiny.is_reflected());
+    }
+
+    #[test_case]
+    fn test_basis_round_trip(_: &mut crate::Gba) {
+        let matrix = AffineMatrix {
+            a: num!(1.5),
+            b: num!(-0.25),
+            c: num!(0.75),
+            d: num!(2.125),
+            x: num!(-30.5),
+            y: num!(12.),
+        };
+
+        let (origin, x_basis, y_basis) = matrix.to_basis();
+        assert_eq!(origin, transform_point(&matrix, (0, 0).into()));
+        assert_eq!(x_basis, transform_point(&matrix, (1, 0).into()) - origin);
+        assert_eq!(y_basis, transform_point(&matrix, (0, 1).into()) - origin);
+
+        assert_eq!(AffineMatrix::from_basis(origin, x_basis, y_basis), matrix);
+    }
+
+    #[test_case]
+    fn test_from_rotation_deg_lut(_: &mut crate::Gba) {
+        assert_eq!(
+            AffineMatrix::from_rotation_deg_lut(0),
+            AffineMatrix::identity()
+        );
+        assert_eq!(
+            AffineMatrix::from_rotation_deg_lut(90),
+            AffineMatrix {
+                a: 0.into(),
+                b: (-1).into(),
+                c: 1.into(),
+                d: 0.into(),
+                x: 0.into(),
+                y: 0.into(),
+            }
+        );
+        assert_eq!(
+            AffineMatrix::from_rotation_deg_lut(450),
+            AffineMatrix::from_rotation_deg_lut(90)
+        );
+        assert_eq!(
+            AffineMatrix::from_rotation_deg_lut(180),
+            AffineMatrix::from_scale((-1, -1).into())
+        );
+        let thirty_degrees = AffineMatrix::from_rotation_deg_lut(30);
+        assert_eq!(thirty_degrees.c, num!(0.5));
+        assert_approx_eq!(thirty_degrees.a, num!(0.8660254), num!(0.01));
+    }
+
+    #[test_case]
+    fn test_background_accessors(_: &mut crate::Gba) {
+        let matrix = AffineMatrixBackground::from_raw(1, -2, 3, -4, 5, -6);
+
+        assert_eq!(matrix.a(), Num::from_raw(1));
+        assert_eq!(matrix.b(), Num::from_raw(-2));
+        assert_eq!(matrix.c(), Num::from_raw(3));
+        assert_eq!(matrix.d(), Num::from_raw(-4));
+        assert_eq!(matrix.x(), Num::from_raw(5));
+        assert_eq!(matrix.y(), Num::from_raw(-6));
+    }
+
+    #[test_case]
+    fn test_bilinear_sample_centre_is_average(_: &mut crate::Gba) {
+        let rgb = |r: u16, g: u16, b: u16| r | (g << 5) | (b << 10);
+        let texture = [[rgb(8, 0, 0), rgb(0, 8, 0)], [rgb(0, 0, 8), rgb(4, 4, 4)]];
+        let get = |x: i32, y: i32| texture[y as usize][x as usize];
+
+        assert_eq!(
+            bilinear_sample(get, (num!(0.5), num!(0.5)).into()),
+            rgb(3, 3, 3)
+        );
+    }
+
+    #[test_case]
+    fn test_bilinear_sample_on_texel_is_exact(_: &mut crate::Gba) {
+        let texture = [[0x1234, 0x7fff], [0x0000, 0x7fff]];
+        let get = |x: i32, y: i32| texture[y.clamp(0, 1) as usize][x.clamp(0, 1) as usize];
+
+        assert_eq!(bilinear_sample(get, (num!(0.), num!(0.)).into()), 0x1234);
+        assert_eq!(bilinear_sample(get, (num!(1.), num!(0.)).into()), 0x7fff);
+    }
+
+    #[test_case]
+    fn test_whole_turn_rotation_equals_identity(_: &mut crate::Gba) {
+        for turns in [num!(0.), num!(1.), num!(-1.), num!(3.)] {
+            assert_eq!(
+                AffineMatrix::from_rotation::<8>(turns),
+                AffineMatrix::identity()
+            );
+        }
+
+        assert_eq!(
+            AffineMatrix::from_rotation::<8>(num!(0.5)),
+            AffineMatrix::from_scale((num!(-1.), num!(-1.)).into())
+        );
+    }
+
+    #[test_case]
+    fn test_interpolate(_: &mut crate::Gba) {
+        let from = AffineMatrix::identity();
+        let to = AffineMatrix::from_scale((num!(3.), num!(-1.)).into())
+            * AffineMatrix::from_translation((num!(8.), num!(-4.)).into());
+
+        assert_eq!(from.interpolate(to, num!(0.)), from);
+        assert_eq!(from.interpolate(to, num!(1.)), to);
+
+        let halfway = from.interpolate(to, num!(0.5));
+        assert_eq!((halfway.a, halfway.b), (num!(2.), num!(0.)));
+        assert_eq!((halfway.c, halfway.d), (num!(0.), num!(0.)));
+        assert_eq!((halfway.x, halfway.y), (num!(-12.), num!(-2.)));
+    }
+
+    #[test_case]
+    fn test_transform_timeline_samples_between_keyframes(_: &mut crate::Gba) {
+        let start = AffineMatrix::from_translation((num!(10.), num!(0.)).into());
+        let end = AffineMatrix::from_scale((num!(2.), num!(2.)).into());
+
+        let mut timeline = TransformTimeline::new();
+        assert_eq!(timeline.sample(5), AffineMatrix::identity());
+
+        timeline.add_keyframe(40, end);
+        timeline.add_keyframe(20, start);
+
+        assert_eq!(timeline.sample(30), start.interpolate(end, num!(0.5)));
+        assert_eq!(timeline.sample(20), start);
+        assert_eq!(timeline.sample(0), start);
+        assert_eq!(timeline.sample(100), end);
+    }
+
+    #[test_case]
+    fn test_bg_affine_syscall_args(_: &mut crate::Gba) {
+        let transform_origin = (num!(12.5), num!(-3.)).into();
+        let position = (num!(20.75), num!(-4.25)).into();
+        let scale = (num!(2.), num!(0.5)).into();
+        let rotation = num!(-0.25);
+
+        let (origin_arg, position_arg, scale_arg, rotation_arg) =
+            AffineMatrixBackground::bg_affine_syscall_args(
+                transform_origin,
+                position,
+                scale,
+                rotation,
+            );
+
+        assert_eq!(origin_arg, transform_origin);
+        assert_eq!(position_arg, Vector2D::new(20, -5));
+        assert_eq!(scale_arg, (num!(2.), num!(0.5)).into());
+        assert_eq!(rotation_arg, num!(0.75));
+
+        assert_eq!(
+            crate::syscall::bg_affine_matrix(origin_arg, position_arg, scale_arg, rotation_arg),
+            AffineMatrixBackground::from_scale_rotation_position(
+                transform_origin,
+                scale,
+                rotation,
+                position
+            )
+        );
+    }
+
+    #[test_case]
+    fn test_absurd_inputs_are_not_sane(_: &mut crate::Gba) {
+        // the test runner can't catch panics, so check what the debug
+        // assertions in the constructors check instead
+        assert!(is_sane::<8>(num!(-200.)));
+        assert!(is_sane::<16>(num!(1.5)));
+        assert!(!is_sane(Num::<i32, 8>::new(1_000_000)));
+        assert!(!is_sane(Num::<i32, 8>::new(-40_000)));
+    }
+
+    #[test_case]
+    fn test_object_le_bytes_round_trip(_: &mut crate::Gba) {
+        let matrix = AffineMatrixObject {
+            a: Num::from_raw(0x0102),
+            b: Num::from_raw(-2),
+            c: Num::from_raw(i16::MIN),
+            d: Num::from_raw(i16::MAX),
+        };
+
+        let bytes = matrix.to_le_bytes();
+        assert_eq!(bytes, [0x02, 0x01, 0xfe, 0xff, 0x00, 0x80, 0xff, 0x7f]);
+        assert_eq!(AffineMatrixObject::from_le_bytes(bytes), matrix);
+    }
+
+    #[test_case]
+    fn test_affine_slot_allocator_runs_out(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let mut allocator = object.affine_slots();
+
+        let mut slots: Vec<_> = (0..AFFINE_SLOT_COUNT)
+            .map(|_| allocator.allocate().unwrap())
+            .collect();
+        assert_eq!(allocator.allocate(), Err(AffineSlotsExhausted(())));
+        assert_eq!(
+            object.affine_slots().allocate(),
+            Err(AffineSlotsExhausted(()))
+        );
+
+        let slot = slots.remove(5);
+        let index = slot.index();
+        allocator.free(slot);
+        assert_eq!(allocator.allocate().map(|slot| slot.index()), Ok(index));
+    }
+
+    #[test_case]
+    fn test_affine_slot_allocators_share_slots(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let mut first = object.affine_slots();
+        let mut second = object.affine_slots();
+
+        let a = first.allocate().unwrap();
+        let b = second.allocate().unwrap();
+        assert_ne!(a.index(), b.index());
+
+        // freeing through either allocator returns it to the controller
+        let index = a.index();
+        second.free(a);
+        assert_eq!(first.allocate().map(|slot| slot.index()), Ok(index));
+    }
+
+    #[test_case]
+    fn test_affine_slot_allocator_writes_to_slot(gba: &mut crate::Gba) {
+        let object = gba.display.object.get();
+        let mut allocator = object.affine_slots();
+        let slot = allocator.allocate().unwrap();
+
+        let matrix = AffineMatrix::from_scale((num!(2.), num!(-0.5)).into()).to_object_wrapping();
+        allocator.write(&slot, matrix);
+
+        let base = usize::from(slot.index()) * 16 + 3;
+        let read = |offset: usize| OBJECT_AFFINE_MEMORY.get(base + offset * 4);
+        assert_eq!([read(0), read(1), read(2), read(3)], [0x200, 0, 0, 0xff80]);
+    }
+
+    #[test_case]
+    fn test_array_round_trip(_: &mut crate::Gba) {
+        let matrix = AffineMatrix::from_rotation::<8>(num!(0.125))
+            * AffineMatrix::from_translation((num!(3.5), num!(-2.)).into());
+
+        let array = matrix.to_array();
+        assert_eq!(array[0], matrix.a);
+        assert_eq!(array[5], matrix.y);
+        assert_eq!(AffineMatrix::from_array(array), matrix);
+    }
+
+    #[test_case]
+    fn test_world_to_screen(_: &mut crate::Gba) {
+        let full_screen = Rect::new((0, 0).into(), (WIDTH, HEIGHT).into());
+        assert_eq!(
+            world_to_screen(&AffineMatrix::identity(), full_screen),
+            AffineMatrix::identity()
+        );
+
+        // a quarter of the screen in the bottom right shows the whole view
+        let quarter = Rect::new(
+            (WIDTH / 2, HEIGHT / 2).into(),
+            (WIDTH / 2, HEIGHT / 2).into(),
+        );
+        let camera = AffineMatrix::from_translation((num!(-10.), num!(-20.)).into());
+        let matrix = world_to_screen(&camera, quarter);
+
+        assert_eq!(
+            transform_point(&matrix, (WIDTH / 2, HEIGHT / 2).into()),
+            (10, 20).into()
+        );
+        assert_eq!(
+            transform_point(&matrix, (WIDTH, HEIGHT).into()),
+            (WIDTH + 10, HEIGHT + 20).into()
+        );
+    }
+
+    #[test_case]
+    fn test_saturating_change_base(_: &mut crate::Gba) {
+        let max = Num::from_raw(i16::MAX);
+        let min = Num::from_raw(i16::MIN);
+
+        assert_eq!(saturating_change_base(num!(-3.25)), num!(-3.25));
+        assert_eq!(saturating_change_base(max.change_base()), max);
+        assert_eq!(saturating_change_base(min.change_base()), min);
+        assert_eq!(saturating_change_base(Num::from_raw(0x8000)), max);
+        assert_eq!(saturating_change_base(num!(1000.)), max);
+        assert_eq!(saturating_change_base(Num::from_raw(-0x8001)), min);
+        assert_eq!(saturating_change_base(num!(-1000.)), min);
+
+        let matrix = AffineMatrix::from_scale((num!(1000.), num!(-1000.)).into());
+        let object = matrix.to_object_clamped();
+        assert_eq!((object.a, object.d), (max, min));
+    }
+
+    #[test_case]
+    fn test_from_integer_scale(_: &mut crate::Gba) {
+        assert_eq!(
+            AffineMatrix::from_integer_scale(1),
+            AffineMatrix::identity()
+        );
+        assert_eq!(
+            AffineMatrix::from_integer_scale(2),
+            AffineMatrix::from_scale((num!(0.5), num!(0.5)).into())
+        );
+
+        let zoomed = AffineMatrix::from_integer_scale(4);
+        assert_eq!(
+            transform_point(&zoomed, (num!(12.), num!(40.)).into()),
+            (num!(3.), num!(10.)).into()
+        );
+    }
+
+    // The matrices map from a point on the screen to the point of the texture
+    // which is shown there, so they look backwards compared to transforming
+    // the texture itself. These pin down what each one looks like on screen.
+
+    #[test_case]
+    fn test_scale_shows_texture_smaller(_: &mut crate::Gba) {
+        let matrix = AffineMatrix::from_scale((num!(2.), num!(4.)).into());
+
+        // the texture at (20, 40) is shown at (10, 10), so it looks shrunk
+        assert_eq!(
+            transform_point(&matrix, (num!(10.), num!(10.)).into()),
+            (num!(20.), num!(40.)).into()
+        );
+    }
+
+    #[test_case]
+    fn test_rotation_turns_texture_anticlockwise(_: &mut crate::Gba) {
+        let quarter_turn = AffineMatrix::from_rotation::<8>(num!(0.25));
+
+        // the texture's x axis is shown pointing up the screen, and its y axis
+        // pointing right, which is an anticlockwise turn with y pointing down
+        assert_eq!(
+            transform_point(&quarter_turn, (num!(0.), num!(-1.)).into()),
+            (num!(1.), num!(0.)).into()
+        );
+        assert_eq!(
+            transform_point(&quarter_turn, (num!(1.), num!(0.)).into()),
+            (num!(0.), num!(1.)).into()
+        );
+    }
+
+    #[test_case]
+    fn test_translation_moves_texture_to_position(_: &mut crate::Gba) {
+        let position = (num!(30.), num!(-8.)).into();
+        let matrix = AffineMatrix::from_translation(position);
+
+        // the origin of the texture is shown at the position
+        assert_eq!(
+            transform_point(&matrix, position),
+            (num!(0.), num!(0.)).into()
+        );
+        assert_eq!(
+            transform_point(&matrix, (num!(0.), num!(0.)).into()),
+            (num!(-30.), num!(8.)).into()
+        );
+    }
+
+    #[test_case]
+    fn test_multiply_applies_right_hand_side_first(_: &mut crate::Gba) {
+        let scale = AffineMatrix::from_scale((num!(2.), num!(2.)).into());
+        let translation = AffineMatrix::from_translation((num!(5.), num!(0.)).into());
+
+        // the screen point is translated and then the result scaled
+        assert_eq!(
+            transform_point(&(scale * translation), (num!(5.), num!(1.)).into()),
+            (num!(0.), num!(2.)).into()
+        );
+        assert_eq!(
+            transform_point(&(translation * scale), (num!(5.), num!(1.)).into()),
+            (num!(5.), num!(2.)).into()
+        );
+    }
+
+    #[test_case]
+    fn test_background_lerp(_: &mut crate::Gba) {
+        let from = AffineMatrixBackground::from_raw(256, -3, 7, i16::MIN, -1000, 20);
+        let to = AffineMatrixBackground::from_raw(-256, 5, 7, i16::MAX, 3000, -21);
+
+        assert_eq!(from.lerp(&to, num!(0.)), from);
+        assert_eq!(from.lerp(&to, num!(1.)), to);
+        assert_eq!(from.lerp(&to, num!(0.5)).to_raw(), (0, 1, 7, -1, 1000, -1));
+
+        // 100,000 pixels either side of the origin
+        let far_left = AffineMatrixBackground::from_raw(256, 0, 0, 256, -25_600_000, 0);
+        let far_right = AffineMatrixBackground::from_raw(256, 0, 0, 256, 25_600_000, 0);
+        assert_eq!(
+            far_left.lerp(&far_right, num!(0.75)).to_raw(),
+            (256, 0, 0, 256, 12_800_000, 0)
+        );
+    }
+
+    #[test_case]
+    fn test_angle_matrix_cache_gets_nearest(_: &mut crate::Gba) {
+        let cache = AngleMatrixCache::<8>::new();
+        let eighth = |n: i32| AffineMatrix::from_rotation(Num::<i32, 16>::new(n) / 8);
+
+        assert_eq!(cache.get::<8>(num!(0.)), AffineMatrix::identity());
+        assert_eq!(cache.get::<8>(num!(0.13)), eighth(1));
+        assert_eq!(cache.get::<8>(num!(0.3)), eighth(2));
+        assert_eq!(cache.get::<16>(num!(0.5)), eighth(4));
+        assert_eq!(cache.get::<8>(num!(0.99)), AffineMatrix::identity());
+        assert_eq!(cache.get::<8>(num!(-0.1)), eighth(7));
+    }
+
+    #[test_case]
+    fn test_skew(_: &mut crate::Gba) {
+        let skew_x = AffineMatrix::from_skew_x(num!(0.5));
+        assert_eq!(
+            transform_point(&skew_x, (num!(3.), num!(10.)).into()),
+            (num!(8.), num!(10.)).into()
+        );
+        assert_eq!(
+            transform_point(&skew_x, (num!(3.), num!(-4.)).into()),
+            (num!(1.), num!(-4.)).into()
+        );
+
+        let skew_y = AffineMatrix::from_skew_y(num!(-2.));
+        assert_eq!(
+            transform_point(&skew_y, (num!(3.), num!(10.)).into()),
+            (num!(3.), num!(4.)).into()
+        );
+    }
+
+    #[test_case]
+    fn test_background_layer_registers(_: &mut crate::Gba) {
+        // the affine registers are write only, so they can't be read back to
+        // check what was written
+        assert_eq!(BackgroundLayer::Bg2.register_address(), 0x0400_0020);
+        assert_eq!(BackgroundLayer::Bg3.register_address(), 0x0400_0030);
+
+        AffineMatrixBackground::IDENTITY.commit_to_bg(BackgroundLayer::Bg3);
+    }
+
+    #[test_case]
+    fn test_read_from_bg_returns_committed_matrix(_: &mut crate::Gba) {
+        let matrix = AffineMatrixBackground::from_raw(0x80, -0x20, 0x30, 0x180, 12 << 8, -40);
+
+        matrix.commit_to_bg(BackgroundLayer::Bg2);
+        AffineMatrixBackground::IDENTITY.commit_to_bg(BackgroundLayer::Bg3);
+
+        assert_eq!(AffineMatrixBackground::read_from_bg2(), matrix);
+        assert_eq!(
+            AffineMatrixBackground::read_from_bg3(),
+            AffineMatrixBackground::IDENTITY
+        );
+
+        AffineMatrixBackground::IDENTITY.commit_to_bg(BackgroundLayer::Bg2);
+        assert_eq!(
+            AffineMatrixBackground::read_from_bg2(),
+            AffineMatrixBackground::IDENTITY
+        );
+    }
+
+    /// Multiplication the straightforward way, which [multiply] should match
+    fn reference_multiply(lhs: AffineMatrix, rhs: AffineMatrix) -> AffineMatrix {
+        AffineMatrix {
+            a: lhs.a * rhs.a + lhs.b * rhs.c,
+            b: lhs.a * rhs.b + lhs.b * rhs.d,
+            c: lhs.c * rhs.a + lhs.d * rhs.c,
+            d: lhs.c * rhs.b + lhs.d * rhs.d,
+            x: lhs.a * rhs.x + lhs.b * rhs.y + lhs.x,
+            y: lhs.c * rhs.x + lhs.d * rhs.y + lhs.y,
+        }
+    }
+
+    fn multiply_test_matrices() -> [AffineMatrix; 5] {
+        let raw = |a, b, c, d, x, y| AffineMatrix {
+            a: Num::from_raw(a),
+            b: Num::from_raw(b),
+            c: Num::from_raw(c),
+            d: Num::from_raw(d),
+            x: Num::from_raw(x),
+            y: Num::from_raw(y),
+        };
+
+        [
+            AffineMatrix::identity(),
+            AffineMatrix::from_rotation::<8>(num!(0.3)),
+            raw(-1, 255, -257, 3, -12345, 678),
+            raw(0x7fff, -0x8000, 1, -1, 0x12_3456, -0x65_4321),
+            AffineMatrix::from_scale((num!(-0.75), num!(100.5)).into())
+                * AffineMatrix::from_translation((num!(-30.25), num!(7.125)).into()),
+        ]
+    }
+
+    #[test_case]
+    fn test_multiply_matches_reference(_: &mut crate::Gba) {
+        for lhs in multiply_test_matrices() {
+            for rhs in multiply_test_matrices() {
+                assert_eq!(lhs * rhs, reference_multiply(lhs, rhs));
+            }
+        }
+    }
+
+    #[test_case]
+    fn test_multiply_is_faster_than_reference(gba: &mut crate::Gba) {
+        use crate::timer::Divider;
+        use core::hint::black_box;
+
+        let mut timers = gba.timers.timers();
+        // timer 3 counts the overflows of timer 2, so together they make a
+        // cycle counter which won't wrap during the test
+        timers.timer2.set_divider(Divider::Divider1);
+        timers.timer3.set_cascade(true).set_enabled(true);
+        timers.timer2.set_enabled(true);
+
+        let now = || loop {
+            let high = timers.timer3.value();
+            let low = timers.timer2.value();
+            if timers.timer3.value() == high {
+                break u32::from(high) << 16 | u32::from(low);
+            }
+        };
+
+        let cycles = |multiply: fn(AffineMatrix, AffineMatrix) -> AffineMatrix| {
+            let matrices = multiply_test_matrices();
+            let start = now();
+            for &lhs in &matrices {
+                for &rhs in &matrices {
+                    black_box(multiply(black_box(lhs), black_box(rhs)));
+                }
+            }
+            now() - start
+        };
+
+        let reference = cycles(reference_multiply);
+        let optimised = cycles(multiply);
+
+        timers.timer2.set_enabled(false);
+        timers.timer3.set_enabled(false);
+
+        if let Some(mut mgba) = crate::mgba::Mgba::new() {
+            mgba.print(
+                format_args!("25 multiplies: {optimised} cycles, reference {reference} cycles"),
+                crate::mgba::DebugLevel::Info,
+            )
+            .unwrap();
+        }
+        assert!(
+            optimised < reference,
+            "multiply took {optimised} cycles, but the reference took {reference}"
+        );
+    }
+
+    #[test_case]
+    fn test_chained_methods_match_multiplication(_: &mut crate::Gba) {
+        let angle: Num<i32, 8> = num!(0.125);
+        let scale: Vector2D<Num<i32, 8>> = (num!(2.), num!(0.5)).into();
+        let position: Vector2D<Num<i32, 8>> = (num!(12.), num!(-3.5)).into();
+
+        let chained = AffineMatrix::identity()
+            .rotated(angle)
+            .scaled(scale)
+            .translated(position);
+
+        let explicit = AffineMatrix::from_translation(position)
+            * AffineMatrix::from_scale(scale)
+            * AffineMatrix::from_rotation(angle)
+            * AffineMatrix::identity();
+
+        assert_eq!(chained, explicit);
+    }
+
+    #[test_case]
+    fn test_tiny_rotation_survives_with_16_fractional_bits(_: &mut crate::Gba) {
+        // a 4096th of a turn, where the sine is around 0.0015
+        let angle = Num::<i32, 16>::from_raw(16);
+
+        let precise = AffineMatrixN::<16>::from_rotation(angle);
+        assert_ne!(precise.c, 0.into());
+        assert_ne!(precise, AffineMatrixN::identity());
+
+        let coarse = AffineMatrix::from_rotation(angle);
+        assert_eq!(coarse.c, 0.into());
+        assert_eq!(precise.to_affine_matrix().c, coarse.c);
+
+        for i in 0..64 {
+            let angle = Num::<i32, 8>::new(i) / 64;
+            assert_eq!(
+                AffineMatrixN::<8>::from_rotation(angle).to_affine_matrix(),
+                AffineMatrix::from_rotation(angle),
+                "same polynomial as AffineMatrix at {angle:?}"
+            );
+        }
+    }
+
+    #[test_case]
+    fn test_affine_matrix_n_round_trips_through_affine_matrix(_: &mut crate::Gba) {
+        let matrix = AffineMatrix::from_rotation::<8>(num!(0.1))
+            * AffineMatrix::from_translation((num!(3.5), num!(-20.25)).into());
+
+        let wide: AffineMatrixN<16> = matrix.into();
+        assert_eq!(AffineMatrix::from(wide), matrix);
+        assert_eq!(
+            (wide * AffineMatrixN::identity()).to_affine_matrix(),
+            matrix
+        );
+    }
+
+    #[test_case]
+    fn test_from_scale_rotation_position_rounded_rounds_to_nearest(_: &mut crate::Gba) {
+        let background = |position: Vector2D<Num<i32, 8>>, rounded: bool| {
+            let origin = (num!(10.), num!(20.)).into();
+            let scale = (num!(1.5), num!(1.5)).into();
+            let rotation = num!(0.1);
+
+            if rounded {
+                AffineMatrixBackground::from_scale_rotation_position_rounded(
+                    origin, scale, rotation, position,
+                )
+            } else {
+                AffineMatrixBackground::from_scale_rotation_position(
+                    origin, scale, rotation, position,
+                )
+            }
+        };
+
+        let position = (num!(0.6), num!(-3.6)).into();
+        assert_eq!(
+            background(position, true),
+            background((num!(1.), num!(-4.)).into(), false)
+        );
+        assert_eq!(
+            background(position, false),
+            background((num!(0.), num!(-4.)).into(), false),
+            "the original still floors"
+        );
+        assert_eq!(
+            background((num!(0.4), num!(-3.4)).into(), true),
+            background((num!(0.), num!(-3.)).into(), false)
+        );
+    }
+
+    #[test_case]
+    fn test_rotating_about_screen_center_keeps_center_fixed(_: &mut crate::Gba) {
+        let center: Vector2D<Num<i32, 8>> = (120, 80).into();
+
+        for i in 0..8 {
+            let rotation = Num::new(i) / 8;
+            for scale in [num!(1.), num!(0.5), num!(2.)] {
+                let matrix = AffineMatrixBackground::rotating_about_screen_center(
+                    rotation,
+                    (scale, scale).into(),
+                )
+                .to_affine_matrix();
+
+                assert_approx_eq!(transform_point(&matrix, center), center, num!(0.05));
+            }
+        }
+    }
+
+    #[test_case]
+    fn test_animate_yields_each_frame_from_start_to_end(_: &mut crate::Gba) {
+        let start = AffineMatrix::from_translation((num!(-10.), num!(4.)).into());
+        let end = AffineMatrix::from_scale((num!(2.), num!(0.5)).into());
+
+        let frames: Vec<_> = animate(start, end, 5).collect();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0], start);
+        assert_eq!(frames[2], start.interpolate(end, num!(0.5)));
+        assert_eq!(frames[4], end);
+
+        assert_eq!(animate(start, end, 1).collect::<Vec<_>>(), [end]);
+        assert_eq!(animate(start, end, 0).count(), 0);
+    }
+
+    #[test_case]
+    fn test_equal_matrices_hash_equally(_: &mut crate::Gba) {
+        use crate::hash_map::HashMap;
+        use rustc_hash::FxHasher;
+
+        let hash = |matrix: &AffineMatrix| {
+            let mut hasher = FxHasher::default();
+            matrix.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let whole_turn = AffineMatrix::from_rotation::<8>(num!(1.));
+        assert_eq!(whole_turn, AffineMatrix::identity());
+        assert_eq!(hash(&whole_turn), hash(&AffineMatrix::identity()));
+
+        let mut cache = HashMap::new();
+        cache.insert(AffineMatrix::from_scale((num!(2.), num!(2.)).into()), 1);
+        cache.insert(whole_turn, 2);
+
+        assert_eq!(cache.get(&AffineMatrix::identity()), Some(&2));
+        assert_eq!(
+            cache.get(&AffineMatrix::from_scale((num!(2.), num!(2.)).into())),
+            Some(&1)
+        );
+        assert_eq!(
+            cache.get(&AffineMatrix::from_scale((num!(3.), num!(2.)).into())),
+            None
+        );
+    }
+
+    #[test_case]
+    fn test_transformed_radius_of_rotated_square_is_half_diagonal(_: &mut crate::Gba) {
+        let half_size = (num!(8.), num!(8.)).into();
+
+        // an eighth of a turn, exactly rather than with the approximation
+        // which from_rotation uses
+        let (cos, sin) = (num!(0.7071), num!(0.7071));
+        let rotated = AffineMatrix::from_array([cos, -sin, sin, cos, 0.into(), 0.into()]);
+        assert_approx_eq!(
+            rotated.transformed_radius(half_size).unwrap(),
+            num!(11.3137),
+            num!(0.1)
+        );
+
+        // the matrix maps the screen onto the sprite, so halving the scale
+        // doubles the size of the sprite
+        let magnified = AffineMatrix::from_scale((num!(0.5), num!(0.5)).into());
+        assert_approx_eq!(
+            magnified.transformed_radius(half_size).unwrap(),
+            num!(22.6274),
+            num!(0.2)
+        );
+
+        let quarter_turn = AffineMatrix::from_rotation::<8>(num!(0.25));
+        assert_eq!(
+            quarter_turn.transformed_radius(half_size),
+            Some(num!(11.3125))
+        );
+
+        let corners = AffineMatrix::identity()
+            .sprite_corners((num!(8.), num!(4.)).into())
+            .unwrap();
+        assert_eq!(
+            corners,
+            [
+                (num!(-8.), num!(-4.)).into(),
+                (num!(8.), num!(-4.)).into(),
+                (num!(8.), num!(4.)).into(),
+                (num!(-8.), num!(4.)).into(),
+            ]
+        );
+
+        // the determinant of this is less than the smallest Num<i32, 8>
+        let tiny = AffineMatrix::from_scale((num!(0.03125), num!(0.03125)).into());
+        assert_eq!(
+            tiny.sprite_corners((num!(2.), num!(1.)).into()).unwrap()[2],
+            (num!(64.), num!(32.)).into()
+        );
+
+        let vanished = AffineMatrix::from_scale((num!(0.), num!(0.)).into());
+        assert_eq!(vanished.sprite_corners(half_size), None);
+        assert_eq!(vanished.transformed_radius(half_size), None);
+    }
+
+    #[test_case]
+    fn test_quantize_for_object_ignores_tiny_differences(_: &mut crate::Gba) {
+        let scaled = |x: Num<i32, 8>| AffineMatrix::from_scale((x, num!(1.)).into());
+        let step = num!(0.015625);
+        let tiny = Num::from_raw(1);
+
+        let object = scaled(num!(1.)).quantize_for_object(step);
+        assert_eq!(scaled(num!(1.) + tiny).quantize_for_object(step), object);
+        assert_eq!(scaled(num!(1.) - tiny).quantize_for_object(step), object);
+        assert_eq!(object, AffineMatrix::identity().to_object_wrapping());
+
+        assert_ne!(scaled(num!(1.0625)).quantize_for_object(step), object);
+        assert_eq!(
+            scaled(num!(1.0625) - tiny).quantize_for_object(step),
+            scaled(num!(1.0625)).quantize_for_object(step),
+        );
+
+        let clamped = scaled(num!(500.)).quantize_for_object(step);
+        assert_eq!(clamped.a.to_raw(), i16::MAX - 3);
+
+        // the hardware's own precision changes nothing
+        let matrix = scaled(num!(1.) + tiny);
+        assert_eq!(matrix.quantize_for_object(tiny), matrix.to_object_clamped());
+    }
+
+    #[test_case]
+    fn test_rotation_matrix_removes_scale_and_translation(_: &mut crate::Gba) {
+        let epsilon = num!(0.02);
+
+        for i in 0..16 {
+            let angle: Num<i32, 8> = Num::new(i) / 16;
+            // from_rotation is an approximation whose axes aren't quite unit
+            // length, so compare against it normalised
+            let rotation = AffineMatrix::from_rotation(angle).rotation_matrix();
+
+            for scale in [(num!(2.), num!(0.5)), (num!(1.5), num!(3.))] {
+                let matrix = AffineMatrix::from_translation((num!(30.), num!(-7.)).into())
+                    * AffineMatrix::from_rotation(angle)
+                    * AffineMatrix::from_scale(scale.into());
+
+                assert_approx_eq!(matrix.rotation_matrix(), rotation, epsilon);
+            }
+
+            assert_approx_eq!(rotation.x_axis().magnitude_squared(), num!(1.), epsilon);
+            assert_approx_eq!(rotation.y_axis().magnitude_squared(), num!(1.), epsilon);
+        }
+
+        let quarter_turn = AffineMatrix::from_rotation::<8>(num!(0.25));
+        assert_approx_eq!(
+            (quarter_turn * AffineMatrix::from_scale((num!(4.), num!(4.)).into()))
+                .rotation_matrix(),
+            quarter_turn,
+            epsilon
+        );
+    }
+
+    // the same transfers as ScanlineAffineDma::start_frame starts, but to
+    // successive matrices in memory rather than the write only registers, so
+    // what each row received can be read back
+    fn start_transfers_to_memory(scanlines: &ScanlineAffineDma, dest: *mut AffineMatrixBackground) {
+        unsafe {
+            dma::hblank_dma0_copy32(
+                scanlines.matrices[1..].as_ptr().cast(),
+                dest.cast(),
+                ScanlineAffineDma::WORDS_PER_MATRIX,
+                false,
+            );
+        }
+    }
+
+    #[test_case]
+    fn test_scanline_affine_dma_copies_a_matrix_each_row(_: &mut crate::Gba) {
+        use crate::display::busy_wait_for_vblank;
+
+        let row_matrix =
+            |row: i32| AffineMatrixBackground::from_raw(1 << 8, 0, 0, 1 << 8, row, -row);
+        let mut scanlines =
+            ScanlineAffineDma::new(BackgroundLayer::Bg2, (0..HEIGHT).map(row_matrix));
+        scanlines.matrices_mut()[20] = AffineMatrixBackground::IDENTITY;
+
+        let mut received = alloc::vec![AffineMatrixBackground::IDENTITY; HEIGHT as usize];
+
+        busy_wait_for_vblank();
+        start_transfers_to_memory(&scanlines, received.as_mut_ptr());
+        busy_wait_for_vblank();
+        dma::stop_dma0();
+
+        for row in 1..HEIGHT {
+            let matrix = unsafe { received.as_ptr().add(row as usize - 1).read_volatile() };
+            let expected = if row == 20 {
+                AffineMatrixBackground::IDENTITY
+            } else {
+                row_matrix(row)
+            };
+            assert_eq!(matrix, expected, "row {row} received the wrong matrix");
+        }
+
+        busy_wait_for_vblank();
+        scanlines.start_frame();
+        assert_eq!(AffineMatrixBackground::read_from_bg2(), row_matrix(0));
+    }
+
+    #[test_case]
+    fn test_from_look_at_points_x_axis_at_target(_: &mut crate::Gba) {
+        let from = (num!(50.), num!(40.)).into();
+
+        for (to, towards) in [
+            ((num!(80.), num!(40.)), (num!(60.), num!(40.))),
+            ((num!(50.), num!(90.)), (num!(50.), num!(50.))),
+            ((num!(20.), num!(40.)), (num!(40.), num!(40.))),
+            ((num!(50.), num!(0.)), (num!(50.), num!(30.))),
+        ] {
+            let matrix = AffineMatrix::from_look_at(from, to.into());
+
+            assert_eq!(
+                transform_point(&matrix, from),
+                (num!(0.), num!(0.)).into(),
+                "the texture's origin is at from"
+            );
+            assert_approx_eq!(
+                transform_point(&matrix, towards.into()),
+                (num!(10.), num!(0.)).into(),
+                num!(0.01)
+            );
+        }
+
+        // the approximate rotation doesn't keep lengths, but the direction
+        // towards the target is still along the x axis of the texture
+        let diagonal = AffineMatrix::from_look_at(from, (num!(80.), num!(70.)).into());
+        let towards = transform_point(&diagonal, (num!(57.), num!(47.)).into());
+        assert_approx_eq!(towards.y, num!(0.), num!(0.01));
+        assert!(
+            towards.x > num!(0.),
+            "{towards:?} should be along the x axis"
+        );
+
+        assert_eq!(
+            AffineMatrix::from_look_at(from, from),
+            AffineMatrix::from_translation(from)
+        );
+    }
+
+    #[test_case]
+    fn test_inverse_undoes_the_matrix(_: &mut crate::Gba) {
+        let scale = AffineMatrix::from_scale((num!(2.), num!(0.5)).into());
+        assert_eq!(
+            scale.inverse(),
+            Some(AffineMatrix::from_scale((num!(0.5), num!(2.)).into()))
+        );
+
+        let translation = AffineMatrix::from_translation((num!(3.5), num!(-8.)).into());
+        assert_eq!(
+            translation.inverse(),
+            Some(AffineMatrix::from_translation(
+                (num!(-3.5), num!(8.)).into()
+            ))
+        );
+
+        let matrix = AffineMatrix::from_rotation::<8>(num!(0.3)) * scale * translation;
+        assert_approx_eq!(
+            matrix * matrix.inverse().unwrap(),
+            AffineMatrix::identity(),
+            num!(0.05)
+        );
+
+        let singular = AffineMatrix::from_scale((num!(0.), num!(1.)).into());
+        assert_eq!(singular.inverse(), None);
+    }
+
+    #[test_case]
+    fn test_fuzz_affine_matrix_invariants(_: &mut crate::Gba) {
+        use crate::rng::RandomNumberGenerator;
+
+        let mut rng = RandomNumberGenerator::new_with_seed([
+            0x1234_5678,
+            0x9abc_def0,
+            0xdead_beef,
+            0x0bad_f00d,
+        ]);
+        let mut random = |range: core::ops::Range<i32>| {
+            range.start + rng.gen().rem_euclid(range.end - range.start)
+        };
+
+        // matrices made from rotations, scales and translations are well
+        // conditioned enough for their inverses to be accurate
+        let mut random_matrix = || {
+            let rotation: Num<i32, 8> = Num::from_raw(random(0..256));
+            let scale = Vector2D::new(
+                Num::from_raw(random(128..512)),
+                Num::from_raw(random(128..512)),
+            );
+            let position = Vector2D::new(
+                Num::from_raw(random(-256 * 256..256 * 256)),
+                Num::from_raw(random(-256 * 256..256 * 256)),
+            );
+
+            AffineMatrix::from_rotation(rotation)
+                .scaled(scale)
+                .translated(position)
+        };
+
+        let determinant = |matrix: AffineMatrix| -> Num<i32, 8> {
+            Num::from_raw((matrix.raw_determinant() >> 8) as i32)
+        };
+
+        for _ in 0..1000 {
+            let (lhs, rhs) = (random_matrix(), random_matrix());
+            let product = lhs * rhs;
+
+            assert_eq!(product, reference_multiply(lhs, rhs));
+
+            // the coefficients are each rounded, which affects the determinant
+            // in proportion to its size
+            let expected = determinant(lhs) * determinant(rhs);
+            let tolerance = expected.abs() / 16 + num!(0.05);
+            assert_approx_eq!(determinant(product), expected, tolerance);
+
+            for matrix in [lhs, product] {
+                let inverse = matrix.inverse().expect("matrix should be invertible");
+                assert_approx_eq!(matrix * inverse, AffineMatrix::identity(), num!(0.1));
+
+                // the other way around, the rounding of the inverse is made
+                // worse the further the matrix translates
+                let undone = inverse * matrix;
+                let tolerance = matrix.position().manhattan_distance() / 64 + num!(0.1);
+                assert_approx_eq!(undone.x_axis(), (num!(1.), num!(0.)).into(), num!(0.05));
+                assert_approx_eq!(undone.y_axis(), (num!(0.), num!(1.)).into(), num!(0.05));
+                assert_approx_eq!(undone.position(), (num!(0.), num!(0.)).into(), tolerance);
+
+                assert_eq!(AffineMatrix::from_array(matrix.to_array()), matrix);
+                assert_eq!(
+                    matrix.try_to_background().map(|bg| bg.to_affine_matrix()),
+                    Ok(matrix)
+                );
+
+                let object = matrix.to_object_wrapping();
+                assert_eq!(
+                    AffineMatrixObject::from_le_bytes(object.to_le_bytes()),
+                    object
+                );
+            }
+        }
+    }
+
+    #[test_case]
+    fn test_to_forward_convention_twice_is_original(_: &mut crate::Gba) {
+        let scale = AffineMatrix::from_scale((num!(2.), num!(4.)).into());
+        let translation = AffineMatrix::from_translation((num!(10.), num!(-3.)).into());
+        let matrix = translation * scale;
+
+        // taking a point from the screen to the texture and back again
+        let forward = matrix.to_forward_convention().unwrap();
+        assert_eq!(
+            transform_point(
+                &forward,
+                transform_point(&matrix, (num!(7.), num!(5.)).into())
+            ),
+            (num!(7.), num!(5.)).into()
+        );
+        assert_eq!(forward.to_forward_convention(), Some(matrix));
+
+        let rotated = AffineMatrix::from_rotation::<8>(num!(0.2)) * translation;
+        assert_approx_eq!(
+            rotated
+                .to_forward_convention()
+                .and_then(|forward| forward.to_forward_convention())
+                .unwrap(),
+            rotated,
+            num!(0.05)
+        );
+
+        let vanished = AffineMatrix::from_scale((num!(0.), num!(0.)).into());
+        assert_eq!(vanished.to_forward_convention(), None);
+    }
+
+    #[test_case]
+    fn test_homogeneous_round_trips(_: &mut crate::Gba) {
+        let matrix = AffineMatrix::from_array([
+            num!(1.5),
+            num!(-0.25),
+            num!(0.75),
+            num!(2.),
+            num!(-12.5),
+            num!(40.),
+        ]);
+
+        assert_eq!(
+            matrix.to_homogeneous(),
+            [
+                [num!(1.5), num!(-0.25), num!(-12.5)],
+                [num!(0.75), num!(2.), num!(40.)],
+                [num!(0.), num!(0.), num!(1.)],
+            ]
+        );
+        assert_eq!(
+            AffineMatrix::from_homogeneous(matrix.to_homogeneous()),
+            Some(matrix)
+        );
+
+        let mut projective = matrix.to_homogeneous();
+        projective[2][0] = num!(0.5);
+        assert_eq!(AffineMatrix::from_homogeneous(projective), None);
+
+        let mut scaled = matrix.to_homogeneous();
+        scaled[2][2] = num!(2.);
+        assert_eq!(AffineMatrix::from_homogeneous(scaled), None);
+    }
+
+    #[test_case]
+    fn test_from_scale_aspect_scales_x(_: &mut crate::Gba) {
+        let scale: Vector2D<Num<i32, 8>> = (num!(1.5), num!(0.5)).into();
+
+        assert_eq!(
+            AffineMatrix::from_scale_aspect(scale, num!(1.)),
+            AffineMatrix::from_scale(scale)
+        );
+        assert_eq!(
+            AffineMatrix::from_scale_aspect(scale, num!(2.)),
+            AffineMatrix::from_scale((num!(3.), num!(0.5)).into())
+        );
+    }
+
+    #[test_case]
+    fn test_background_conversion_is_lossy_detects_lost_bits(_: &mut crate::Gba) {
+        assert!(!AffineMatrix::from_rotation::<8>(num!(0.125)).background_conversion_is_lossy());
+        assert!(
+            !AffineMatrix::from_translation((num!(100000.), num!(0.)).into())
+                .background_conversion_is_lossy()
+        );
+        assert!(AffineMatrix::from_scale((num!(200.), num!(1.)).into())
+            .background_conversion_is_lossy());
 
-        assert_eq!(e.position(), position);
-        assert_eq!(d * d, AffineMatrix::identity());
+        let precise = AffineMatrixN::<16>::from_rotation::<16>(num!(0.01));
+        assert!(precise.background_conversion_is_lossy());
+        assert!(
+            !AffineMatrixN::<16>::from(precise.to_affine_matrix()).background_conversion_is_lossy()
+        );
+        assert!(!AffineMatrixN::<4>::identity().background_conversion_is_lossy());
     }
 }
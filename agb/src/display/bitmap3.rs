@@ -1,13 +1,17 @@
-use crate::memory_mapped::MemoryMapped2DArray;
+use crate::{
+    dma,
+    fixnum::{Num, Rect, Vector2D},
+    memory_mapped::{MemoryMapped, MemoryMapped2DArray},
+};
 
 use super::{
-    set_graphics_mode, set_graphics_settings, DisplayMode, GraphicsSettings, HEIGHT, WIDTH,
+    busy_wait_for_vblank, set_graphics_mode, set_graphics_settings, video::Video, DisplayMode,
+    GraphicsSettings, Priority, HEIGHT, WIDTH,
 };
 
-use core::convert::TryInto;
-
 const BITMAP_MODE_3: MemoryMapped2DArray<u16, { WIDTH as usize }, { HEIGHT as usize }> =
     unsafe { MemoryMapped2DArray::new(0x600_0000) };
+const BG2_CONTROL: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x0400_000C) };
 
 #[non_exhaustive]
 pub struct Bitmap3 {}
@@ -19,11 +23,1274 @@ impl Bitmap3 {
         Bitmap3 {}
     }
 
+    /// Switches the display to mode 3 for code which manages the display modes
+    /// itself, the same as [`Video::bitmap3`]. Exclusive access to the
+    /// [`Video`], which only [`Gba`][crate::Gba] gives out, is the token which
+    /// makes this safe as whatever holds it decides which mode is active.
+    /// Switching to another mode through the same [`Video`] leaves this
+    /// [`Bitmap3`] writing to memory which is no longer displayed as a bitmap,
+    /// so drop it first.
+    pub fn acquire(_video: &mut Video) -> Self {
+        unsafe { Self::new() }
+    }
+
+    /// Waits until the start of the next vertical blank. The screen isn't
+    /// being drawn during the vertical blank, so drawing then, rather than
+    /// while the screen is being sent to the display, avoids tearing.
+    pub fn wait_for_vblank(&self) {
+        busy_wait_for_vblank();
+    }
+
+    /// Waits for the next vertical blank and then performs the drawing in
+    /// `draw`, to batch up drawing to avoid tearing. The vertical blank is
+    /// only long enough to redraw a small part of the screen, so anything
+    /// still being drawn after it ends can still tear.
+    pub fn draw_in_vblank(&mut self, draw: impl FnOnce(&mut Self)) {
+        self.wait_for_vblank();
+        draw(self);
+    }
+
     /// Draws point to screen at (x, y) coordinates with colour and panics if
     /// (x, y) is out of the bounds of the screen.
     pub fn draw_point(&mut self, x: i32, y: i32, colour: u16) {
-        let x = x.try_into().unwrap();
-        let y = y.try_into().unwrap();
-        BITMAP_MODE_3.set(x, y, colour);
+        draw::draw_point(self, x, y, colour);
+    }
+
+    /// Draws each of the points with its colour, which is cheaper than calling
+    /// [`draw_point`][Bitmap3::draw_point] for each of them. Any points which
+    /// are out of the bounds of the screen are skipped.
+    pub fn draw_points(&mut self, points: &[(Vector2D<i32>, u16)]) {
+        draw::draw_points(self, points);
+    }
+
+    /// Draws a point to the screen at the pixel containing the sub pixel
+    /// position `pos`, so the same as [`draw_point`][Bitmap3::draw_point] with
+    /// the coordinates floored. Panics if this is out of the bounds of the
+    /// screen.
+    pub fn draw_point_sub(&mut self, pos: Vector2D<Num<i32, 8>>, colour: u16) {
+        self.draw_point(pos.x.floor(), pos.y.floor(), colour);
+    }
+
+    /// Sets the priority of the bitmap, which is displayed on background 2,
+    /// allowing objects to be drawn in front of or behind it.
+    pub fn set_background_priority(&mut self, priority: Priority) {
+        BG2_CONTROL.set_bits(priority as u16, 2, 0);
+    }
+
+    /// Fills the rectangle with colour, clipping it to the screen. Rectangles
+    /// which span the whole width of the screen are filled much faster, since
+    /// those rows are next to each other in memory.
+    pub fn fill_rect(&mut self, rect: Rect<i32>, colour: u16) {
+        let Some(rect) = ScreenClip::SCREEN.clip_rect(rect) else {
+            return;
+        };
+
+        if rect.size.x == WIDTH {
+            // rows are a whole number of words long, so this can be filled a
+            // pair of pixels at a time
+            let start = unsafe {
+                BITMAP_MODE_3
+                    .as_ptr()
+                    .add((rect.position.y * WIDTH) as usize)
+                    .cast::<u32>()
+            };
+            let pair = u32::from(colour) | (u32::from(colour) << 16);
+
+            for i in 0..(rect.size.y * WIDTH / 2) as usize {
+                unsafe { start.add(i).write_volatile(pair) };
+            }
+        } else {
+            for y in rect.position.y..rect.position.y + rect.size.y {
+                for x in rect.position.x..rect.position.x + rect.size.x {
+                    draw::Pixels::set(self, x, y, colour);
+                }
+            }
+        }
+    }
+
+    /// Fills the convex polygon with the given vertices with colour, clipping
+    /// it to the screen. The outline of the polygon is included in the filled
+    /// area. Nothing is drawn if all the points are collinear.
+    pub fn fill_convex_polygon(&mut self, points: &[Vector2D<i32>], colour: u16) {
+        draw::fill_convex_polygon(self, points, colour);
+    }
+
+    /// Copies the whole screen into `out` in row major order, for example to
+    /// take a screenshot. Panics if `out` isn't exactly `WIDTH * HEIGHT` long.
+    pub fn copy_to_slice(&self, out: &mut [u16]) {
+        assert_eq!(
+            out.len(),
+            (WIDTH * HEIGHT) as usize,
+            "slice must be the size of the screen"
+        );
+
+        let screen = BITMAP_MODE_3.region(0, 0, WIDTH as usize, HEIGHT as usize);
+        for (out, colour) in out.iter_mut().zip(screen) {
+            *out = colour;
+        }
+    }
+
+    /// Copies the pixels in `src` so that its top left corner is at `dest`,
+    /// which works even when the two regions overlap. Any part of either region
+    /// which is off the screen is skipped.
+    pub fn copy_within(&mut self, src: Rect<i32>, dest: Vector2D<i32>) {
+        draw::copy_within(self, src, dest);
+    }
+
+    /// Fills the whole screen with a checkerboard of the two colours, where
+    /// each square is `cell` pixels wide and the top left square is `a`.
+    /// Panics if `cell` isn't positive.
+    pub fn fill_checkerboard(&mut self, a: u16, b: u16, cell: i32) {
+        draw::fill_checkerboard(self, a, b, cell);
+    }
+
+    /// Adds each channel of colour to the point at (x, y), with each channel
+    /// saturating at 31 rather than wrapping around. This brightens what is
+    /// already there, which is useful for glowing effects. Panics if (x, y) is
+    /// out of the bounds of the screen.
+    pub fn add_point(&mut self, x: i32, y: i32, colour: u16) {
+        draw::add_point(self, x, y, colour);
+    }
+
+    /// Fills the slice of the circle of `radius` around `center` which goes
+    /// from `start_angle` to `end_angle`, clipping it to the screen. Angles are
+    /// in revolutions clockwise from the positive x axis, since y grows down
+    /// the screen, and the slice always goes clockwise from the start to the
+    /// end, wrapping around through 0 if the end comes before the start. An
+    /// end a whole revolution or more after the start fills the whole circle.
+    pub fn fill_arc(
+        &mut self,
+        center: Vector2D<i32>,
+        radius: i32,
+        start_angle: Num<i32, 8>,
+        end_angle: Num<i32, 8>,
+        colour: u16,
+    ) {
+        draw::fill_arc(self, center, radius, start_angle, end_angle, colour);
+    }
+
+    /// Exclusive ors the colour of the point at (x, y) with `mask`, so that
+    /// doing it again with the same mask puts back what was there before. This
+    /// is useful for drawing things like cursors which need to be removed
+    /// again without keeping a copy of what they covered. Panics if (x, y) is
+    /// out of the bounds of the screen.
+    pub fn xor_point(&mut self, x: i32, y: i32, mask: u16) {
+        draw::xor_point(self, x, y, mask);
+    }
+
+    /// Exclusive ors the outline of the rectangle with `mask`, clipping it to
+    /// the screen, for the rectangle of a selection which is being dragged out.
+    /// As with [`xor_point`][Bitmap3::xor_point], doing it again erases it.
+    pub fn xor_rect(&mut self, rect: Rect<i32>, mask: u16) {
+        draw::xor_rect(self, rect, mask);
+    }
+
+    /// Fills the whole screen with a gradient from `top` to `bottom`, using
+    /// ordered dithering to hide the banding there would otherwise be where
+    /// the colour steps between the few shades of each channel.
+    pub fn fill_vertical_gradient_dithered(&mut self, top: u16, bottom: u16) {
+        draw::fill_vertical_gradient_dithered(self, top, bottom);
+    }
+
+    /// Draws a run length encoded image into `dest`, clipping it to the
+    /// screen. The image is made up of runs of three bytes each, a count
+    /// followed by a little endian colour, which mean that the next count
+    /// pixels are all that colour. The runs fill `dest` a row at a time from
+    /// the top left, and any pixels beyond the end of `dest` are ignored.
+    /// Panics if `data` isn't a whole number of runs.
+    pub fn blit_rle(&mut self, data: &[u8], dest: Rect<i32>) {
+        draw::blit_rle(self, data, dest);
+    }
+
+    /// Multiplies each channel of every pixel in `rect` by `factor`, clamping
+    /// them to the range of a channel, which can be used to fade part of the
+    /// screen in or out. A factor of 0 makes it black and 1 leaves it as it
+    /// is. This reads back from video memory so is slow for large areas.
+    pub fn adjust_brightness(&mut self, rect: Rect<i32>, factor: Num<i32, 8>) {
+        draw::adjust_brightness(self, rect, factor);
+    }
+
+    /// Copies an image into `dest`, clipping it to the screen. The image is
+    /// in row major order with rows that are `src_width` pixels long, and the
+    /// top left `dest.size` pixels of it are copied. Each row is copied using
+    /// DMA channel 3, during which the CPU is paused, so this is much faster
+    /// than drawing the pixels one at a time. Panics if the image is narrower
+    /// than `dest` or doesn't have enough rows.
+    pub fn blit_dma(&mut self, src: &[u16], src_width: usize, dest: Rect<i32>) {
+        assert!(
+            src_width >= dest.size.x.max(0) as usize,
+            "image must be at least as wide as dest"
+        );
+
+        let Some(clipped) = ScreenClip::SCREEN.clip_rect(dest) else {
+            return;
+        };
+
+        let offset = clipped.position - dest.position;
+        let width = clipped.size.x as usize;
+
+        for y in 0..clipped.size.y {
+            let start = (offset.y + y) as usize * src_width + offset.x as usize;
+            let row = &src[start..start + width];
+
+            unsafe {
+                dma::dma_copy16(
+                    row.as_ptr(),
+                    BITMAP_MODE_3
+                        .as_ptr()
+                        .add(((clipped.position.y + y) * WIDTH + clipped.position.x) as usize),
+                    width,
+                );
+            }
+        }
+    }
+}
+
+impl draw::Pixels for Bitmap3 {
+    fn get(&self, x: i32, y: i32) -> u16 {
+        BITMAP_MODE_3.get(x as usize, y as usize)
+    }
+
+    fn set(&mut self, x: i32, y: i32, colour: u16) {
+        BITMAP_MODE_3.set(x as usize, y as usize, colour);
+    }
+}
+
+/// A screen sized framebuffer in normal memory with the same drawing methods
+/// as [Bitmap3], which is then copied to the screen all at once using
+/// [`present`][SoftFramebuffer::present]. Reading from normal memory is much
+/// faster than reading from video memory, so this suits effects which read
+/// back what has been drawn. It also avoids tearing as long as the copy is
+/// done during the vertical blank.
+pub struct SoftFramebuffer<'a> {
+    buffer: &'a mut [u16],
+}
+
+impl<'a> SoftFramebuffer<'a> {
+    /// Draws to `buffer`, which is in row major order. Panics if `buffer`
+    /// isn't exactly `WIDTH * HEIGHT` long.
+    pub fn new(buffer: &'a mut [u16]) -> Self {
+        assert_eq!(
+            buffer.len(),
+            (WIDTH * HEIGHT) as usize,
+            "buffer must be the size of the screen"
+        );
+
+        SoftFramebuffer { buffer }
+    }
+
+    /// Copies the framebuffer to the screen using DMA
+    pub fn present(&self, _bitmap: &mut Bitmap3) {
+        unsafe {
+            dma::dma_copy16(
+                self.buffer.as_ptr(),
+                BITMAP_MODE_3.as_ptr(),
+                self.buffer.len(),
+            );
+        }
+    }
+
+    /// Draws point to the framebuffer at (x, y) coordinates with colour and
+    /// panics if (x, y) is out of the bounds of the screen.
+    pub fn draw_point(&mut self, x: i32, y: i32, colour: u16) {
+        draw::draw_point(self, x, y, colour);
+    }
+
+    /// Draws each of the points with its colour, skipping any which are out of
+    /// the bounds of the screen, as [`Bitmap3::draw_points`].
+    pub fn draw_points(&mut self, points: &[(Vector2D<i32>, u16)]) {
+        draw::draw_points(self, points);
+    }
+
+    /// Draws a point at the pixel containing the sub pixel position `pos`, so
+    /// the same as [`draw_point`][SoftFramebuffer::draw_point] with the
+    /// coordinates floored.
+    pub fn draw_point_sub(&mut self, pos: Vector2D<Num<i32, 8>>, colour: u16) {
+        self.draw_point(pos.x.floor(), pos.y.floor(), colour);
+    }
+
+    /// Fills the convex polygon with the given vertices with colour, as
+    /// [`Bitmap3::fill_convex_polygon`].
+    pub fn fill_convex_polygon(&mut self, points: &[Vector2D<i32>], colour: u16) {
+        draw::fill_convex_polygon(self, points, colour);
+    }
+
+    /// Copies the pixels in `src` so that its top left corner is at `dest`, as
+    /// [`Bitmap3::copy_within`].
+    pub fn copy_within(&mut self, src: Rect<i32>, dest: Vector2D<i32>) {
+        draw::copy_within(self, src, dest);
+    }
+
+    /// Fills the whole framebuffer with a checkerboard, as
+    /// [`Bitmap3::fill_checkerboard`].
+    pub fn fill_checkerboard(&mut self, a: u16, b: u16, cell: i32) {
+        draw::fill_checkerboard(self, a, b, cell);
+    }
+
+    /// Adds each channel of colour to the point at (x, y), as
+    /// [`Bitmap3::add_point`].
+    pub fn add_point(&mut self, x: i32, y: i32, colour: u16) {
+        draw::add_point(self, x, y, colour);
+    }
+
+    /// Fills a slice of a circle, as [`Bitmap3::fill_arc`].
+    pub fn fill_arc(
+        &mut self,
+        center: Vector2D<i32>,
+        radius: i32,
+        start_angle: Num<i32, 8>,
+        end_angle: Num<i32, 8>,
+        colour: u16,
+    ) {
+        draw::fill_arc(self, center, radius, start_angle, end_angle, colour);
+    }
+
+    /// Exclusive ors the point at (x, y) with `mask`, as [`Bitmap3::xor_point`].
+    pub fn xor_point(&mut self, x: i32, y: i32, mask: u16) {
+        draw::xor_point(self, x, y, mask);
+    }
+
+    /// Exclusive ors the outline of the rectangle with `mask`, as
+    /// [`Bitmap3::xor_rect`].
+    pub fn xor_rect(&mut self, rect: Rect<i32>, mask: u16) {
+        draw::xor_rect(self, rect, mask);
+    }
+
+    /// Fills the whole framebuffer with a dithered gradient, as
+    /// [`Bitmap3::fill_vertical_gradient_dithered`].
+    pub fn fill_vertical_gradient_dithered(&mut self, top: u16, bottom: u16) {
+        draw::fill_vertical_gradient_dithered(self, top, bottom);
+    }
+
+    /// Draws a run length encoded image into `dest`, as [`Bitmap3::blit_rle`].
+    pub fn blit_rle(&mut self, data: &[u8], dest: Rect<i32>) {
+        draw::blit_rle(self, data, dest);
+    }
+
+    /// Scales the brightness of every pixel in `rect`, as
+    /// [`Bitmap3::adjust_brightness`].
+    pub fn adjust_brightness(&mut self, rect: Rect<i32>, factor: Num<i32, 8>) {
+        draw::adjust_brightness(self, rect, factor);
+    }
+}
+
+impl draw::Pixels for SoftFramebuffer<'_> {
+    fn get(&self, x: i32, y: i32) -> u16 {
+        self.buffer[(y * WIDTH + x) as usize]
+    }
+
+    fn set(&mut self, x: i32, y: i32, colour: u16) {
+        self.buffer[(y * WIDTH + x) as usize] = colour;
+    }
+}
+
+/// The last `N` positions of something moving, for drawing a trail behind it
+/// which fades out towards the oldest position. Once `N` positions have been
+/// pushed, each new one replaces the oldest.
+pub struct Trail<const N: usize> {
+    points: [Vector2D<i32>; N],
+    len: usize,
+    oldest: usize,
+}
+
+impl<const N: usize> Trail<N> {
+    #[must_use]
+    /// Creates an empty trail
+    pub fn new() -> Self {
+        Trail {
+            points: [Vector2D::new(0, 0); N],
+            len: 0,
+            oldest: 0,
+        }
+    }
+
+    /// Records `point` as the newest position, forgetting the oldest if the
+    /// trail is already full
+    pub fn push(&mut self, point: Vector2D<i32>) {
+        if N == 0 {
+            return;
+        }
+
+        if self.len < N {
+            self.points[(self.oldest + self.len) % N] = point;
+            self.len += 1;
+        } else {
+            self.points[self.oldest] = point;
+            self.oldest = (self.oldest + 1) % N;
+        }
+    }
+
+    #[must_use]
+    /// The number of positions recorded, which is at most `N`
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    /// Whether no positions have been recorded since the trail was created or
+    /// cleared
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Forgets all the recorded positions
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.oldest = 0;
+    }
+
+    /// The recorded positions from the oldest to the newest
+    pub fn iter(&self) -> impl Iterator<Item = Vector2D<i32>> + '_ {
+        (0..self.len).map(move |i| self.points[(self.oldest + i) % N])
+    }
+
+    /// Draws the trail in `colour`, with the newest position at full
+    /// brightness and each older one dimmer than the last, scaling the colour
+    /// as [`Bitmap3::adjust_brightness`] does. Positions off the screen are
+    /// skipped.
+    pub fn draw(&self, bitmap: &mut Bitmap3, colour: u16) {
+        for (age, point) in self.iter().enumerate() {
+            if ScreenClip::SCREEN.clip_point(point).is_none() {
+                continue;
+            }
+
+            let brightness = Num::new(age as i32 + 1) / self.len as i32;
+            bitmap.draw_point(point.x, point.y, draw::scale_colour(colour, brightness));
+        }
+    }
+}
+
+impl<const N: usize> Default for Trail<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The drawing shared between [Bitmap3] and [SoftFramebuffer]
+mod draw {
+    use super::{Num, Rect, ScreenClip, Vector2D, HEIGHT, WIDTH};
+
+    /// Reading and writing pixels, which are always within the screen
+    pub(super) trait Pixels {
+        fn get(&self, x: i32, y: i32) -> u16;
+        fn set(&mut self, x: i32, y: i32, colour: u16);
+    }
+
+    pub(super) fn draw_point(pixels: &mut impl Pixels, x: i32, y: i32, colour: u16) {
+        let point = ScreenClip::SCREEN
+            .clip_point((x, y).into())
+            .expect("point should be within the screen");
+        pixels.set(point.x, point.y, colour);
+    }
+
+    pub(super) fn draw_points(pixels: &mut impl Pixels, points: &[(Vector2D<i32>, u16)]) {
+        for &(point, colour) in points {
+            if let Some(point) = ScreenClip::SCREEN.clip_point(point) {
+                pixels.set(point.x, point.y, colour);
+            }
+        }
+    }
+
+    pub(super) fn add_point(pixels: &mut impl Pixels, x: i32, y: i32, colour: u16) {
+        let point = ScreenClip::SCREEN
+            .clip_point((x, y).into())
+            .expect("point should be within the screen");
+        let existing = pixels.get(point.x, point.y);
+
+        let sum = [0, 5, 10].into_iter().fold(0, |sum, shift| {
+            let channel = ((existing >> shift) & 0x1f) + ((colour >> shift) & 0x1f);
+            sum | (channel.min(0x1f) << shift)
+        });
+
+        pixels.set(point.x, point.y, sum);
+    }
+
+    pub(super) fn fill_arc(
+        pixels: &mut impl Pixels,
+        center: Vector2D<i32>,
+        radius: i32,
+        start_angle: Num<i32, 8>,
+        end_angle: Num<i32, 8>,
+        colour: u16,
+    ) {
+        let sweep = end_angle - start_angle;
+        let full_circle = sweep >= 1.into();
+        let start: Num<i32, 16> = start_angle.rem_euclid(1.into()).change_base();
+        let sweep: Num<i32, 16> = sweep.rem_euclid(1.into()).change_base();
+
+        let bounds = Rect::new(
+            center - (radius, radius).into(),
+            (radius * 2 + 1, radius * 2 + 1).into(),
+        );
+        let Some(bounds) = ScreenClip::SCREEN.clip_rect(bounds) else {
+            return;
+        };
+
+        for y in bounds.position.y..bounds.position.y + bounds.size.y {
+            for x in bounds.position.x..bounds.position.x + bounds.size.x {
+                let (dx, dy) = (x - center.x, y - center.y);
+                if dx * dx + dy * dy > radius * radius {
+                    continue;
+                }
+
+                let angle = Num::<i32, 16>::new(dy).atan2(Num::new(dx));
+                if full_circle || (angle - start).rem_euclid(1.into()) < sweep {
+                    pixels.set(x, y, colour);
+                }
+            }
+        }
+    }
+
+    pub(super) fn xor_point(pixels: &mut impl Pixels, x: i32, y: i32, mask: u16) {
+        let point = ScreenClip::SCREEN
+            .clip_point((x, y).into())
+            .expect("point should be within the screen");
+        let existing = pixels.get(point.x, point.y);
+        pixels.set(point.x, point.y, existing ^ mask);
+    }
+
+    pub(super) fn xor_rect(pixels: &mut impl Pixels, rect: Rect<i32>, mask: u16) {
+        if rect.size.x <= 0 || rect.size.y <= 0 {
+            return;
+        }
+
+        let (left, top) = (rect.position.x, rect.position.y);
+        let (right, bottom) = (left + rect.size.x - 1, top + rect.size.y - 1);
+
+        let mut xor = |x: i32, y: i32| {
+            if let Some(point) = ScreenClip::SCREEN.clip_point((x, y).into()) {
+                let existing = pixels.get(point.x, point.y);
+                pixels.set(point.x, point.y, existing ^ mask);
+            }
+        };
+
+        // each pixel of the outline must be touched exactly once, otherwise the
+        // corners would be flipped back again
+        for x in left..=right {
+            xor(x, top);
+            if bottom != top {
+                xor(x, bottom);
+            }
+        }
+        for y in top + 1..bottom {
+            xor(left, y);
+            if right != left {
+                xor(right, y);
+            }
+        }
+    }
+
+    pub(super) fn fill_convex_polygon(
+        pixels: &mut impl Pixels,
+        points: &[Vector2D<i32>],
+        colour: u16,
+    ) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let edges = || points.iter().zip(points.iter().cycle().skip(1));
+
+        // twice the signed area, which is zero for collinear points
+        let area: i32 = edges().map(|(p0, p1)| p0.x * p1.y - p1.x * p0.y).sum();
+        if area == 0 {
+            return;
+        }
+
+        let top = points.iter().map(|p| p.y).min().unwrap().max(0);
+        let bottom = points.iter().map(|p| p.y).max().unwrap().min(HEIGHT - 1);
+
+        for y in top..=bottom {
+            let mut left = i32::MAX;
+            let mut right = i32::MIN;
+
+            for (p0, p1) in edges() {
+                if y < p0.y.min(p1.y) || y > p0.y.max(p1.y) {
+                    continue;
+                }
+
+                let (x0, x1) = if p0.y == p1.y {
+                    (p0.x, p1.x)
+                } else {
+                    let x = p0.x + (y - p0.y) * (p1.x - p0.x) / (p1.y - p0.y);
+                    (x, x)
+                };
+
+                left = left.min(x0.min(x1));
+                right = right.max(x0.max(x1));
+            }
+
+            if let Some((left, right)) = ScreenClip::SCREEN.clip_hline(left, right, y) {
+                for x in left..=right {
+                    pixels.set(x, y, colour);
+                }
+            }
+        }
+    }
+
+    pub(super) fn copy_within(pixels: &mut impl Pixels, src: Rect<i32>, dest: Vector2D<i32>) {
+        let offset = dest - src.position;
+
+        let Some(src) = ScreenClip::SCREEN.clip_rect(src) else {
+            return;
+        };
+        let Some(dest) = ScreenClip::SCREEN.clip_rect(Rect::new(src.position + offset, src.size))
+        else {
+            return;
+        };
+
+        let left = dest.position.x - offset.x;
+        let top = dest.position.y - offset.y;
+        let right = left + dest.size.x;
+        let bottom = top + dest.size.y;
+
+        // copy the pixels nearest the destination first, so that overlapping
+        // pixels are read before they get overwritten
+        for row in 0..bottom - top {
+            let y = if offset.y > 0 {
+                bottom - 1 - row
+            } else {
+                top + row
+            };
+
+            for column in 0..right - left {
+                let x = if offset.x > 0 {
+                    right - 1 - column
+                } else {
+                    left + column
+                };
+
+                let colour = pixels.get(x, y);
+                pixels.set(x + offset.x, y + offset.y, colour);
+            }
+        }
+    }
+
+    pub(super) fn blit_rle(pixels: &mut impl Pixels, data: &[u8], dest: Rect<i32>) {
+        assert!(
+            data.len() % 3 == 0,
+            "run length encoded data must be made of 3 byte runs"
+        );
+
+        let width = dest.size.x.max(0);
+        let area = width * dest.size.y.max(0);
+
+        let runs = data.chunks_exact(3).flat_map(|run| {
+            let colour = u16::from_le_bytes([run[1], run[2]]);
+            core::iter::repeat(colour).take(run[0].into())
+        });
+
+        for (i, colour) in (0..area).zip(runs) {
+            let point = dest.position + (i % width, i / width).into();
+            if let Some(point) = ScreenClip::SCREEN.clip_point(point) {
+                pixels.set(point.x, point.y, colour);
+            }
+        }
+    }
+
+    pub(super) fn adjust_brightness(
+        pixels: &mut impl Pixels,
+        rect: Rect<i32>,
+        factor: Num<i32, 8>,
+    ) {
+        let Some(rect) = ScreenClip::SCREEN.clip_rect(rect) else {
+            return;
+        };
+
+        for y in rect.position.y..rect.position.y + rect.size.y {
+            for x in rect.position.x..rect.position.x + rect.size.x {
+                let adjusted = scale_colour(pixels.get(x, y), factor);
+                pixels.set(x, y, adjusted);
+            }
+        }
+    }
+
+    /// Multiplies each channel of the colour by `factor`, saturating at full
+    /// brightness
+    pub(super) fn scale_colour(colour: u16, factor: Num<i32, 8>) -> u16 {
+        [0, 5, 10].into_iter().fold(0, |scaled, shift| {
+            let channel = i32::from((colour >> shift) & 0x1f);
+            let channel = ((channel * factor.to_raw()) >> 8).clamp(0, 0x1f) as u16;
+            scaled | (channel << shift)
+        })
+    }
+
+    /// Thresholds for ordered dithering, each out of 16
+    const BAYER: [[u16; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+    pub(super) fn fill_vertical_gradient_dithered(pixels: &mut impl Pixels, top: u16, bottom: u16) {
+        for y in 0..HEIGHT {
+            // each channel at this row in sixteenths
+            let channels = [0, 5, 10].map(|shift| {
+                let top = i32::from((top >> shift) & 0x1f);
+                let bottom = i32::from((bottom >> shift) & 0x1f);
+                (
+                    shift,
+                    (top * 16 + (bottom - top) * 16 * y / (HEIGHT - 1)) as u16,
+                )
+            });
+
+            for x in 0..WIDTH {
+                let threshold = BAYER[(y % 4) as usize][(x % 4) as usize];
+                let colour = channels.iter().fold(0, |colour, &(shift, channel)| {
+                    let value = (channel >> 4) + u16::from(channel & 0xf > threshold);
+                    colour | (value.min(0x1f) << shift)
+                });
+
+                pixels.set(x, y, colour);
+            }
+        }
+    }
+
+    pub(super) fn fill_checkerboard(pixels: &mut impl Pixels, a: u16, b: u16, cell: i32) {
+        assert!(cell > 0, "checkerboard cell size must be positive");
+
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let colour = if (x / cell + y / cell) % 2 == 0 { a } else { b };
+                pixels.set(x, y, colour);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Clips the coordinates of drawing operations to an area with its top left
+/// corner at (0, 0), so that they only touch pixels which are in bounds.
+pub struct ScreenClip {
+    width: i32,
+    height: i32,
+}
+
+impl ScreenClip {
+    /// Clips to the whole of the screen
+    pub const SCREEN: ScreenClip = ScreenClip::new(WIDTH, HEIGHT);
+
+    #[must_use]
+    /// Creates a clip for an area which is `width` by `height` pixels
+    pub const fn new(width: i32, height: i32) -> Self {
+        ScreenClip { width, height }
+    }
+
+    #[must_use]
+    /// Returns `point` if it is in bounds, or [None] if it isn't
+    pub fn clip_point(&self, point: Vector2D<i32>) -> Option<Vector2D<i32>> {
+        let in_bounds = (0..self.width).contains(&point.x) && (0..self.height).contains(&point.y);
+        in_bounds.then_some(point)
+    }
+
+    #[must_use]
+    /// Clips the horizontal line on row `y` between `x0` and `x1` inclusive,
+    /// which can be given in either order. Returns the leftmost and rightmost
+    /// pixels of the clipped line, or [None] if none of it is in bounds.
+    pub fn clip_hline(&self, x0: i32, x1: i32, y: i32) -> Option<(i32, i32)> {
+        if !(0..self.height).contains(&y) {
+            return None;
+        }
+
+        let left = x0.min(x1).max(0);
+        let right = x0.max(x1).min(self.width - 1);
+
+        (left <= right).then_some((left, right))
+    }
+
+    #[must_use]
+    /// Clips the rectangle to the bounds, or returns [None] if none of it is
+    /// in bounds
+    pub fn clip_rect(&self, rect: Rect<i32>) -> Option<Rect<i32>> {
+        Rect::new((0, 0).into(), (self.width, self.height).into())
+            .overlapping_rect(rect)
+            .filter(|clipped| clipped.size.x > 0 && clipped.size.y > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    #[test_case]
+    fn fill_convex_polygon_fills_triangle(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.draw_point(15, 15, 0);
+        bitmap.draw_point(45, 45, 0);
+
+        bitmap.fill_convex_polygon(&[(10, 10).into(), (50, 10).into(), (10, 50).into()], 0x7fff);
+
+        assert_eq!(BITMAP_MODE_3.get(15, 15), 0x7fff, "inside the triangle");
+        assert_eq!(BITMAP_MODE_3.get(10, 50), 0x7fff, "on a vertex");
+        assert_eq!(BITMAP_MODE_3.get(45, 45), 0, "outside the triangle");
+
+        bitmap.draw_point(20, 20, 0);
+        bitmap.fill_convex_polygon(&[(0, 0).into(), (10, 10).into(), (30, 30).into()], 0x1f);
+        assert_eq!(
+            BITMAP_MODE_3.get(20, 20),
+            0,
+            "collinear points draw nothing"
+        );
+    }
+
+    #[test_case]
+    fn fill_checkerboard_alternates_cells(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.fill_checkerboard(0x001f, 0x7c00, 8);
+
+        assert_eq!(BITMAP_MODE_3.get(0, 0), 0x001f);
+        assert_eq!(BITMAP_MODE_3.get(7, 7), 0x001f);
+        assert_eq!(BITMAP_MODE_3.get(8, 0), 0x7c00);
+        assert_eq!(BITMAP_MODE_3.get(0, 8), 0x7c00);
+        assert_eq!(BITMAP_MODE_3.get(8, 8), 0x001f);
+    }
+
+    #[test_case]
+    fn set_background_priority_sets_bg2_control(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.set_background_priority(Priority::P2);
+        assert_eq!(BG2_CONTROL.get() & 0b11, 2);
+
+        bitmap.set_background_priority(Priority::P0);
+        assert_eq!(BG2_CONTROL.get() & 0b11, 0);
+    }
+
+    #[test_case]
+    fn copy_to_slice_reads_whole_screen(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.fill_checkerboard(0, 0, 1);
+        bitmap.draw_point(0, 0, 0x1f);
+        bitmap.draw_point(WIDTH - 1, HEIGHT - 1, 0x7c00);
+
+        // too large for the stack
+        let mut screen = vec![0xffff; (WIDTH * HEIGHT) as usize];
+        bitmap.copy_to_slice(&mut screen);
+
+        assert_eq!(screen[0], 0x1f);
+        assert_eq!(screen[1], 0);
+        assert_eq!(screen[(WIDTH * HEIGHT) as usize - 1], 0x7c00);
+        assert!(screen[1..(WIDTH * HEIGHT) as usize - 1]
+            .iter()
+            .all(|&c| c == 0));
+    }
+
+    #[test_case]
+    fn copy_within_handles_overlap_and_clipping(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.fill_checkerboard(0, 0, 1);
+        bitmap.draw_point(0, 0, 1);
+        bitmap.draw_point(1, 0, 2);
+        bitmap.draw_point(2, 0, 3);
+
+        let row = Rect::new((0, 0).into(), (3, 1).into());
+        let read_row = |x: usize, y: usize| [0, 1, 2].map(|i| BITMAP_MODE_3.get(x + i, y));
+
+        bitmap.copy_within(row, (10, 5).into());
+        assert_eq!(read_row(10, 5), [1, 2, 3], "non overlapping");
+
+        bitmap.copy_within(Rect::new((10, 5).into(), (3, 1).into()), (11, 5).into());
+        assert_eq!(read_row(10, 5), [1, 1, 2], "overlapping to the right");
+        assert_eq!(BITMAP_MODE_3.get(13, 5), 3);
+
+        bitmap.copy_within(Rect::new((11, 5).into(), (3, 1).into()), (10, 5).into());
+        assert_eq!(read_row(10, 5), [1, 2, 3], "overlapping to the left");
+
+        bitmap.copy_within(row, (-1, 1).into());
+        assert_eq!(read_row(0, 1), [2, 3, 0], "clipped at the destination");
+
+        bitmap.copy_within(Rect::new((-2, 0).into(), (3, 1).into()), (20, 0).into());
+        assert_eq!(read_row(20, 0), [0, 0, 1], "clipped at the source");
+    }
+
+    #[test_case]
+    fn draw_point_sub_floors_position(gba: &mut crate::Gba) {
+        use crate::fixnum::num;
+
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.draw_point(3, 4, 0);
+        bitmap.draw_point_sub((num!(3.75), num!(4.5)).into(), 0x3e0);
+        assert_eq!(BITMAP_MODE_3.get(3, 4), 0x3e0);
+
+        bitmap.draw_point(4, 4, 0);
+        bitmap.draw_point_sub((num!(4.), num!(4.99)).into(), 0x1f);
+        assert_eq!(BITMAP_MODE_3.get(4, 4), 0x1f);
+    }
+
+    #[test_case]
+    fn fill_rect_full_width_matches_partial_width(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+        let mut full_width = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut partial_width = vec![0; (WIDTH * HEIGHT) as usize];
+
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0);
+        bitmap.fill_rect(Rect::new((-5, 10).into(), (WIDTH + 10, 4).into()), 0x1234);
+        bitmap.copy_to_slice(&mut full_width);
+
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0);
+        bitmap.fill_rect(Rect::new((0, 10).into(), (100, 4).into()), 0x1234);
+        bitmap.fill_rect(Rect::new((100, 10).into(), (WIDTH, 4).into()), 0x1234);
+        bitmap.copy_to_slice(&mut partial_width);
+
+        assert!(full_width == partial_width);
+        assert_eq!(BITMAP_MODE_3.get(0, 9), 0);
+        assert_eq!(BITMAP_MODE_3.get(0, 10), 0x1234);
+        assert_eq!(BITMAP_MODE_3.get(WIDTH as usize - 1, 13), 0x1234);
+        assert_eq!(BITMAP_MODE_3.get(WIDTH as usize - 1, 14), 0);
+    }
+
+    #[test_case]
+    fn fill_vertical_gradient_dithered_alternates_shades(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.fill_vertical_gradient_dithered(0, 1);
+
+        assert_eq!(BITMAP_MODE_3.get(17, 0), 0, "the top is the top colour");
+        assert_eq!(
+            BITMAP_MODE_3.get(17, HEIGHT as usize - 1),
+            1,
+            "the bottom is the bottom colour"
+        );
+
+        // half way between the two shades, alternating pixels are each shade
+        let row: [u16; 4] = core::array::from_fn(|x| BITMAP_MODE_3.get(x + 100, 80));
+        assert_eq!(row, [1, 0, 1, 0]);
+    }
+
+    #[test_case]
+    fn blit_rle_decodes_runs(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.fill_rect(Rect::new((0, 0).into(), (8, 8).into()), 0);
+
+        // 3 pixels of 0x1234, 0 pixels of 0x7fff and then 5 pixels of 0x001f
+        let data = [3, 0x34, 0x12, 0, 0xff, 0x7f, 5, 0x1f, 0x00];
+        bitmap.blit_rle(&data, Rect::new((1, 2).into(), (4, 2).into()));
+
+        let row = |y: usize| -> [u16; 6] { core::array::from_fn(|x| BITMAP_MODE_3.get(x, y)) };
+        assert_eq!(row(2), [0, 0x1234, 0x1234, 0x1234, 0x1f, 0]);
+        assert_eq!(
+            row(3),
+            [0, 0x1f, 0x1f, 0x1f, 0x1f, 0],
+            "stops at the end of dest"
+        );
+
+        bitmap.blit_rle(&data, Rect::new((-2, 6).into(), (4, 1).into()));
+        assert_eq!(row(6)[..3], [0x1234, 0x1f, 0], "clipped to the screen");
+    }
+
+    #[test_case]
+    fn blit_dma_matches_drawing_each_pixel(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+        let mut with_dma = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut with_cpu = vec![0; (WIDTH * HEIGHT) as usize];
+
+        let src_width = 40;
+        let image: Vec<u16> = (0..src_width * 30).map(|i| i as u16).collect();
+        let dests = [
+            Rect::new((10, 20).into(), (40, 30).into()),
+            Rect::new((-6, 100).into(), (20, 10).into()),
+            Rect::new((WIDTH - 5, HEIGHT - 8).into(), (32, 16).into()),
+        ];
+
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0);
+        for dest in dests {
+            bitmap.blit_dma(&image, src_width, dest);
+        }
+        bitmap.copy_to_slice(&mut with_dma);
+
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0);
+        for dest in dests {
+            for y in 0..dest.size.y {
+                for x in 0..dest.size.x {
+                    let point = dest.position + (x, y).into();
+                    if ScreenClip::SCREEN.clip_point(point).is_some() {
+                        let colour = image[y as usize * src_width + x as usize];
+                        bitmap.draw_point(point.x, point.y, colour);
+                    }
+                }
+            }
+        }
+        bitmap.copy_to_slice(&mut with_cpu);
+
+        assert!(with_dma == with_cpu);
+        assert_eq!(BITMAP_MODE_3.get(10, 21), 40);
+        assert_eq!(BITMAP_MODE_3.get(0, 100), 6, "clipped to the screen");
+    }
+
+    #[test_case]
+    fn adjust_brightness_scales_channels(gba: &mut crate::Gba) {
+        use crate::fixnum::num;
+
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.fill_rect(Rect::new((0, 0).into(), (4, 4).into()), 0x1234);
+
+        bitmap.adjust_brightness(Rect::new((0, 0).into(), (4, 4).into()), num!(1.));
+        assert_eq!(BITMAP_MODE_3.get(3, 3), 0x1234, "1 leaves pixels unchanged");
+
+        // channels of 20, 17 and 4
+        bitmap.adjust_brightness(Rect::new((0, 0).into(), (4, 1).into()), num!(0.5));
+        assert_eq!(BITMAP_MODE_3.get(0, 0), 10 | (8 << 5) | (2 << 10));
+
+        bitmap.adjust_brightness(Rect::new((0, 1).into(), (4, 1).into()), num!(2.));
+        assert_eq!(
+            BITMAP_MODE_3.get(0, 1),
+            31 | (31 << 5) | (8 << 10),
+            "clamped"
+        );
+
+        bitmap.adjust_brightness(Rect::new((-2, 2).into(), (4, 2).into()), num!(0.));
+        assert_eq!(BITMAP_MODE_3.get(1, 3), 0, "0 makes pixels black");
+        assert_eq!(BITMAP_MODE_3.get(2, 3), 0x1234);
+    }
+
+    #[test_case]
+    fn acquire_switches_to_mode_3(gba: &mut crate::Gba) {
+        use super::super::{current_graphics_mode, current_graphics_settings};
+
+        unsafe { set_graphics_mode(DisplayMode::Tiled0) };
+
+        let mut bitmap = Bitmap3::acquire(&mut gba.display.video);
+        assert_eq!(current_graphics_mode(), DisplayMode::Bitmap3);
+        assert_eq!(current_graphics_settings(), GraphicsSettings::LAYER_BG2);
+
+        bitmap.draw_point(1, 2, 0x1f);
+        assert_eq!(BITMAP_MODE_3.get(1, 2), 0x1f);
+    }
+
+    #[test_case]
+    fn trail_evicts_oldest_point(gba: &mut crate::Gba) {
+        let mut trail = Trail::<3>::new();
+        assert!(trail.is_empty());
+
+        for x in 0..4 {
+            trail.push((x, 10).into());
+        }
+
+        assert_eq!(trail.len(), 3);
+        assert_eq!(
+            trail.iter().collect::<Vec<_>>(),
+            vec![(1, 10).into(), (2, 10).into(), (3, 10).into()]
+        );
+
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.fill_rect(Rect::new((0, 0).into(), (4, 11).into()), 0);
+        trail.push((-1, 10).into());
+        trail.draw(&mut bitmap, 0x1e);
+
+        assert_eq!(BITMAP_MODE_3.get(1, 10), 0, "evicted");
+        assert_eq!(BITMAP_MODE_3.get(2, 10), 9, "oldest is dimmest");
+        assert_eq!(BITMAP_MODE_3.get(3, 10), 19);
+
+        trail.clear();
+        assert!(trail.iter().next().is_none());
+    }
+
+    #[test_case]
+    fn fill_arc_fills_slices_of_circle(gba: &mut crate::Gba) {
+        use crate::fixnum::num;
+
+        let mut bitmap = gba.display.video.bitmap3();
+        let center: Vector2D<i32> = (50, 60).into();
+        let radius = 10;
+        let in_circle = |x: i32, y: i32| {
+            let (dx, dy) = (x - center.x, y - center.y);
+            dx * dx + dy * dy <= radius * radius
+        };
+
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0);
+        bitmap.fill_arc(center, radius, num!(0.), num!(1.), 0x1f);
+        for y in 45..75 {
+            for x in 35..65 {
+                let expected = if in_circle(x, y) { 0x1f } else { 0 };
+                assert_eq!(BITMAP_MODE_3.get(x as usize, y as usize), expected);
+            }
+        }
+
+        // the bottom half, going clockwise from the right
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0);
+        bitmap.fill_arc(center, radius, num!(0.), num!(0.5), 0x1f);
+        assert_eq!(BITMAP_MODE_3.get(50, 65), 0x1f);
+        assert_eq!(BITMAP_MODE_3.get(50, 55), 0);
+
+        // wrapping around from the bottom through the right to the top
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0);
+        bitmap.fill_arc(center, radius, num!(0.5), num!(0.25), 0x1f);
+        assert_eq!(BITMAP_MODE_3.get(45, 55), 0x1f, "top left");
+        assert_eq!(BITMAP_MODE_3.get(55, 55), 0x1f, "top right");
+        assert_eq!(BITMAP_MODE_3.get(55, 65), 0x1f, "bottom right");
+        assert_eq!(BITMAP_MODE_3.get(45, 65), 0, "bottom left");
+
+        bitmap.fill_arc((-5, 3).into(), radius, num!(0.), num!(1.), 0x1f);
+        assert_eq!(BITMAP_MODE_3.get(0, 3), 0x1f, "clipped to the screen");
+    }
+
+    #[test_case]
+    fn xor_twice_restores_pixels(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.fill_checkerboard(0x1234, 0x7c1f, 2);
+
+        let mut before = vec![0; (WIDTH * HEIGHT) as usize];
+        bitmap.copy_to_slice(&mut before);
+
+        bitmap.xor_point(5, 5, 0x7fff);
+        assert_eq!(BITMAP_MODE_3.get(5, 5), 0x1234 ^ 0x7fff);
+        bitmap.xor_point(5, 5, 0x7fff);
+        assert_eq!(BITMAP_MODE_3.get(5, 5), 0x1234);
+
+        let rect = Rect::new((-3, 10).into(), (20, 6).into());
+        bitmap.xor_rect(rect, 0x7fff);
+        assert_eq!(BITMAP_MODE_3.get(0, 10), 0x7c1f ^ 0x7fff);
+        assert_eq!(BITMAP_MODE_3.get(16, 15), 0x7c1f ^ 0x7fff, "the corner");
+        assert_eq!(BITMAP_MODE_3.get(16, 12), 0x1234 ^ 0x7fff);
+        assert_eq!(BITMAP_MODE_3.get(8, 12), 0x1234, "inside isn't touched");
+
+        bitmap.xor_rect(rect, 0x7fff);
+        bitmap.xor_rect(Rect::new((100, 100).into(), (1, 1).into()), 0x1f);
+        bitmap.xor_rect(Rect::new((100, 100).into(), (1, 1).into()), 0x1f);
+
+        let mut after = vec![0; (WIDTH * HEIGHT) as usize];
+        bitmap.copy_to_slice(&mut after);
+        assert!(before == after);
+    }
+
+    #[test_case]
+    fn draw_points_skips_out_of_bounds_points(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0);
+
+        bitmap.draw_points(&[
+            ((3, 4).into(), 0x1f),
+            ((-1, 4).into(), 0x3e0),
+            ((WIDTH, 0).into(), 0x3e0),
+            ((0, HEIGHT).into(), 0x3e0),
+            ((WIDTH - 1, HEIGHT - 1).into(), 0x7c00),
+        ]);
+
+        assert_eq!(BITMAP_MODE_3.get(3, 4), 0x1f);
+        assert_eq!(
+            BITMAP_MODE_3.get(WIDTH as usize - 1, HEIGHT as usize - 1),
+            0x7c00
+        );
+
+        let mut screen = vec![0; (WIDTH * HEIGHT) as usize];
+        bitmap.copy_to_slice(&mut screen);
+        assert_eq!(screen.iter().filter(|&&colour| colour != 0).count(), 2);
+    }
+
+    #[test_case]
+    fn add_point_saturates_channels(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.draw_point(5, 6, 0x0c0a);
+        bitmap.add_point(5, 6, 0x0822);
+        assert_eq!(BITMAP_MODE_3.get(5, 6), 0x142c, "adds each channel");
+
+        bitmap.draw_point(5, 6, 0x7c1e);
+        bitmap.add_point(5, 6, 0x0c03);
+        assert_eq!(
+            BITMAP_MODE_3.get(5, 6),
+            0x7c1f,
+            "saturates rather than wrapping"
+        );
+    }
+
+    #[test_case]
+    fn draw_in_vblank_draws_during_vblank(gba: &mut crate::Gba) {
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.draw_in_vblank(|bitmap| {
+            assert!(crate::display::VCOUNT.get() >= HEIGHT as u16);
+            bitmap.draw_point(0, 0, 0x7fff);
+        });
+
+        assert_eq!(BITMAP_MODE_3.get(0, 0), 0x7fff);
+    }
+
+    #[test_case]
+    fn screen_clip_clips_points(_: &mut crate::Gba) {
+        let clip = ScreenClip::SCREEN;
+
+        assert_eq!(clip.clip_point((0, 0).into()), Some((0, 0).into()));
+        assert_eq!(
+            clip.clip_point((WIDTH - 1, HEIGHT - 1).into()),
+            Some((WIDTH - 1, HEIGHT - 1).into())
+        );
+        assert_eq!(clip.clip_point((WIDTH, 0).into()), None);
+        assert_eq!(clip.clip_point((0, HEIGHT).into()), None);
+        assert_eq!(clip.clip_point((-1, 5).into()), None);
+    }
+
+    #[test_case]
+    fn screen_clip_clips_lines(_: &mut crate::Gba) {
+        let clip = ScreenClip::SCREEN;
+
+        assert_eq!(clip.clip_hline(10, 20, 5), Some((10, 20)));
+        assert_eq!(clip.clip_hline(20, 10, 5), Some((10, 20)));
+        assert_eq!(clip.clip_hline(-5, 0, 5), Some((0, 0)));
+        assert_eq!(clip.clip_hline(-5, -1, 5), None);
+        assert_eq!(
+            clip.clip_hline(WIDTH - 1, WIDTH + 10, 5),
+            Some((WIDTH - 1, WIDTH - 1))
+        );
+        assert_eq!(clip.clip_hline(WIDTH, WIDTH + 10, 5), None);
+        assert_eq!(clip.clip_hline(10, 20, -1), None);
+        assert_eq!(clip.clip_hline(10, 20, HEIGHT), None);
+    }
+
+    #[test_case]
+    fn screen_clip_clips_rects(_: &mut crate::Gba) {
+        let clip = ScreenClip::new(10, 10);
+
+        assert_eq!(
+            clip.clip_rect(Rect::new((2, 3).into(), (4, 5).into())),
+            Some(Rect::new((2, 3).into(), (4, 5).into()))
+        );
+        assert_eq!(
+            clip.clip_rect(Rect::new((-2, 8).into(), (4, 5).into())),
+            Some(Rect::new((0, 8).into(), (2, 2).into()))
+        );
+        assert_eq!(
+            clip.clip_rect(Rect::new((10, 0).into(), (4, 5).into())),
+            None
+        );
+        assert_eq!(
+            clip.clip_rect(Rect::new((-4, 0).into(), (4, 5).into())),
+            None
+        );
+        assert_eq!(
+            clip.clip_rect(Rect::new((2, 2).into(), (0, 5).into())),
+            None
+        );
+    }
+
+    #[test_case]
+    fn soft_framebuffer_matches_direct_drawing(gba: &mut crate::Gba) {
+        let triangle = [(10, 10).into(), (200, 30).into(), (50, 150).into()];
+        let region = Rect::new((0, 0).into(), (100, 100).into());
+
+        let mut bitmap = gba.display.video.bitmap3();
+
+        bitmap.fill_checkerboard(0x1f, 0x3e0, 16);
+        bitmap.fill_convex_polygon(&triangle, 0x7c00);
+        bitmap.copy_within(region, (120, 40).into());
+
+        let mut expected = vec![0; (WIDTH * HEIGHT) as usize];
+        bitmap.copy_to_slice(&mut expected);
+
+        bitmap.fill_checkerboard(0, 0, 1);
+
+        let mut buffer = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut framebuffer = SoftFramebuffer::new(&mut buffer);
+
+        framebuffer.fill_checkerboard(0x1f, 0x3e0, 16);
+        framebuffer.fill_convex_polygon(&triangle, 0x7c00);
+        framebuffer.copy_within(region, (120, 40).into());
+        framebuffer.present(&mut bitmap);
+
+        let presented = BITMAP_MODE_3.region(0, 0, WIDTH as usize, HEIGHT as usize);
+        assert!(presented.eq(expected.iter().copied()));
     }
 }
@@ -0,0 +1,364 @@
+use crate::fixnum::{Num, Rect, Vector2D};
+
+use super::{
+    affine::AffineMatrix,
+    bitmap3::{Bitmap3, ScreenClip, SoftFramebuffer},
+    bitmap4::Bitmap4,
+    Font, HEIGHT, WIDTH,
+};
+
+/// Something which can be drawn to a pixel at a time, such as [Bitmap3], which
+/// allows drawing code to be written once for any of the bitmap modes. Shapes
+/// drawn using the provided methods are clipped to the
+/// [`dimensions`][DrawTarget::dimensions] of the target.
+pub trait DrawTarget {
+    /// The type of the colour of a pixel, which is a 15 bit colour or a
+    /// palette index depending on the mode
+    type Colour: Copy;
+
+    /// The width and height of the target in pixels
+    fn dimensions(&self) -> Vector2D<i32>;
+
+    /// Draws a point at (x, y) and panics if (x, y) is out of bounds
+    fn draw_point(&mut self, x: i32, y: i32, colour: Self::Colour);
+
+    /// Draws a line from `start` to `end`, including both ends
+    fn draw_line(&mut self, start: Vector2D<i32>, end: Vector2D<i32>, colour: Self::Colour) {
+        let clip = clip(self);
+
+        let dx = (end.x - start.x).abs();
+        let dy = -(end.y - start.y).abs();
+        let step_x = if start.x < end.x { 1 } else { -1 };
+        let step_y = if start.y < end.y { 1 } else { -1 };
+
+        // bresenham's line algorithm
+        let mut point = start;
+        let mut error = dx + dy;
+        loop {
+            if clip.clip_point(point).is_some() {
+                self.draw_point(point.x, point.y, colour);
+            }
+
+            if point == end {
+                break;
+            }
+
+            let doubled_error = 2 * error;
+            if doubled_error >= dy {
+                error += dy;
+                point.x += step_x;
+            }
+            if doubled_error <= dx {
+                error += dx;
+                point.y += step_y;
+            }
+        }
+    }
+
+    /// Draws `text` in `font` with the top left of the first line at
+    /// `position`, returning where the cursor is after the text. Each letter
+    /// moves the cursor along by its own width, so proportional fonts are
+    /// spaced properly, and a `'\n'` moves it to the start of the next line.
+    fn draw_text(
+        &mut self,
+        font: &Font,
+        text: &str,
+        position: Vector2D<i32>,
+        colour: Self::Colour,
+    ) -> Vector2D<i32> {
+        let clip = clip(self);
+        let width = self.dimensions().x;
+        let mut cursor = position;
+
+        for letter in text.chars() {
+            if letter == '\n' {
+                cursor = (position.x, cursor.y + font.line_height()).into();
+            } else if cursor.x >= width {
+                // nothing more on this line is visible, but the cursor still
+                // needs to end up in the right place
+                cursor.x += font.plot_letter(letter, cursor, |_| {});
+            } else {
+                cursor.x += font.plot_letter(letter, cursor, |point| {
+                    if let Some(point) = clip.clip_point(point) {
+                        self.draw_point(point.x, point.y, colour);
+                    }
+                });
+            }
+        }
+
+        cursor
+    }
+
+    /// Fills the rectangle with colour
+    fn fill_rect(&mut self, rect: Rect<i32>, colour: Self::Colour) {
+        let Some(rect) = clip(self).clip_rect(rect) else {
+            return;
+        };
+
+        for y in rect.position.y..rect.position.y + rect.size.y {
+            for x in rect.position.x..rect.position.x + rect.size.x {
+                self.draw_point(x, y, colour);
+            }
+        }
+    }
+}
+
+/// Draws the [x axis][AffineMatrix::x_axis] of `matrix` in red and its
+/// [y axis][AffineMatrix::y_axis] in green, as lines from `origin` scaled up by
+/// `length`, to help see what a transformation is doing while debugging.
+pub fn draw_transform_gizmo(
+    target: &mut impl DrawTarget<Colour = u16>,
+    matrix: &AffineMatrix,
+    origin: Vector2D<i32>,
+    length: i32,
+) {
+    let end = |axis: Vector2D<_>| origin + (axis * length).floor();
+
+    target.draw_line(origin, end(matrix.y_axis()), 0x03e0);
+    target.draw_line(origin, end(matrix.x_axis()), 0x001f);
+}
+
+/// Wraps a [DrawTarget] so that points and lines are transformed by an
+/// [AffineMatrix] before being plotted, for drawing in a rotated or scaled
+/// coordinate space. The transform starts off as the identity.
+///
+/// The matrix is applied to each point as it is, mapping `(x, y)` to
+/// `x * x_axis + y * y_axis - position`, which is the opposite direction to
+/// the one the hardware uses for backgrounds and objects. To draw what a
+/// matrix built for the hardware would show, use
+/// [`to_forward_convention`][AffineMatrix::to_forward_convention] first.
+pub struct Transformed<'a, T: ?Sized> {
+    target: &'a mut T,
+    transform: AffineMatrix,
+}
+
+impl<'a, T: DrawTarget + ?Sized> Transformed<'a, T> {
+    /// Wraps `target` with the transform starting off as the identity, so
+    /// everything is drawn where it would be without the wrapper
+    pub fn new(target: &'a mut T) -> Self {
+        Self {
+            target,
+            transform: AffineMatrix::identity(),
+        }
+    }
+
+    #[must_use]
+    /// The transform points are currently drawn through
+    pub fn transform(&self) -> AffineMatrix {
+        self.transform
+    }
+
+    /// Sets the transform to draw points through. It maps from the space
+    /// being drawn in to the target, which is the opposite direction to the
+    /// matrices given to the hardware, which map from the screen to the
+    /// texture. Use [`to_forward_convention`][AffineMatrix::to_forward_convention]
+    /// to draw with one of those.
+    pub fn set_transform(&mut self, transform: AffineMatrix) {
+        self.transform = transform;
+    }
+
+    /// Where `point` ends up on the target, rounded to the nearest pixel
+    #[must_use]
+    pub fn apply(&self, point: Vector2D<i32>) -> Vector2D<i32> {
+        let half = Num::new(1) / 2;
+        let transformed = self.transform.x_axis() * point.x + self.transform.y_axis() * point.y
+            - self.transform.position();
+
+        (transformed + (half, half).into()).floor()
+    }
+
+    /// Draws the transformed point, skipping it if it ends up out of bounds
+    pub fn draw_point(&mut self, x: i32, y: i32, colour: T::Colour) {
+        if let Some(point) = clip(self.target).clip_point(self.apply((x, y).into())) {
+            self.target.draw_point(point.x, point.y, colour);
+        }
+    }
+
+    /// Draws the line between the transformed ends, which is where every
+    /// point of the line ends up as affine transformations keep lines straight
+    pub fn draw_line(&mut self, start: Vector2D<i32>, end: Vector2D<i32>, colour: T::Colour) {
+        let (start, end) = (self.apply(start), self.apply(end));
+        self.target.draw_line(start, end, colour);
+    }
+}
+
+fn clip<T: DrawTarget + ?Sized>(target: &T) -> ScreenClip {
+    let dimensions = target.dimensions();
+    ScreenClip::new(dimensions.x, dimensions.y)
+}
+
+impl DrawTarget for Bitmap3 {
+    type Colour = u16;
+
+    fn dimensions(&self) -> Vector2D<i32> {
+        (WIDTH, HEIGHT).into()
+    }
+
+    fn draw_point(&mut self, x: i32, y: i32, colour: u16) {
+        Bitmap3::draw_point(self, x, y, colour);
+    }
+
+    fn fill_rect(&mut self, rect: Rect<i32>, colour: u16) {
+        Bitmap3::fill_rect(self, rect, colour);
+    }
+}
+
+impl DrawTarget for SoftFramebuffer<'_> {
+    type Colour = u16;
+
+    fn dimensions(&self) -> Vector2D<i32> {
+        (WIDTH, HEIGHT).into()
+    }
+
+    fn draw_point(&mut self, x: i32, y: i32, colour: u16) {
+        SoftFramebuffer::draw_point(self, x, y, colour);
+    }
+}
+
+/// Draws to the page which isn't currently being displayed
+impl DrawTarget for Bitmap4<'_> {
+    type Colour = u8;
+
+    fn dimensions(&self) -> Vector2D<i32> {
+        (WIDTH, HEIGHT).into()
+    }
+
+    fn draw_point(&mut self, x: i32, y: i32, colour: u8) {
+        Bitmap4::draw_point(self, x, y, colour);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn draw_shapes(target: &mut impl DrawTarget<Colour = u16>) {
+        target.draw_line((10, 10).into(), (20, 20).into(), 0x1f);
+        target.draw_line((30, 5).into(), (20, 5).into(), 0x3e0);
+        target.draw_line((-10, 100).into(), (WIDTH + 10, 100).into(), 0x7c00);
+        target.fill_rect(Rect::new((-5, 150).into(), (10, 20).into()), 0x7fff);
+    }
+
+    #[test_case]
+    fn draw_line_draws_through_every_pixel(gba: &mut crate::Gba) {
+        let mut buffer = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut framebuffer = SoftFramebuffer::new(&mut buffer);
+        draw_shapes(&mut framebuffer);
+
+        let pixel = |x: i32, y: i32| buffer[(y * WIDTH + x) as usize];
+
+        assert!((10..=20).all(|i| pixel(i, i) == 0x1f), "diagonal line");
+        assert_eq!(pixel(21, 21), 0);
+        assert!((20..=30).all(|x| pixel(x, 5) == 0x3e0), "backwards line");
+        assert!((0..WIDTH).all(|x| pixel(x, 100) == 0x7c00), "clipped line");
+        assert_eq!(pixel(4, 159), 0x7fff, "clipped rectangle");
+        assert_eq!(pixel(5, 159), 0);
+
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.fill_checkerboard(0, 0, 1);
+        draw_shapes(&mut bitmap);
+
+        let mut screen = vec![0; (WIDTH * HEIGHT) as usize];
+        bitmap.copy_to_slice(&mut screen);
+        assert!(
+            screen == buffer,
+            "bitmap 3 draws the same as the framebuffer"
+        );
+    }
+
+    #[test_case]
+    fn draw_transform_gizmo_draws_axes(_: &mut crate::Gba) {
+        use crate::fixnum::num;
+
+        let mut buffer = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut framebuffer = SoftFramebuffer::new(&mut buffer);
+
+        draw_transform_gizmo(
+            &mut framebuffer,
+            &AffineMatrix::identity(),
+            (50, 50).into(),
+            10,
+        );
+        draw_transform_gizmo(
+            &mut framebuffer,
+            &AffineMatrix::from_rotation::<8>(num!(0.25)),
+            (100, 50).into(),
+            20,
+        );
+        draw_transform_gizmo(
+            &mut framebuffer,
+            &AffineMatrix::from_scale((num!(100.), num!(100.)).into()),
+            (0, 0).into(),
+            100,
+        );
+
+        let pixel = |x: i32, y: i32| buffer[(y * WIDTH + x) as usize];
+
+        assert_eq!(pixel(50, 50), 0x1f, "the x axis is drawn over the origin");
+        assert_eq!(pixel(60, 50), 0x1f);
+        assert_eq!(pixel(50, 60), 0x3e0);
+        assert_eq!(pixel(100, 70), 0x1f, "the x axis rotated to point down");
+        assert_eq!(pixel(80, 50), 0x3e0, "the y axis rotated to point left");
+    }
+
+    #[test_case]
+    fn draw_text_advances_by_each_letter_width(_: &mut crate::Gba) {
+        use super::super::FontLetter;
+
+        static LETTERS: [FontLetter; 2] = [
+            FontLetter::new(2, 1, &[0b11], 0, 0, 3),
+            FontLetter::new(4, 1, &[0b1111], 0, 0, 5),
+        ];
+        static FONT: Font = Font::new(&LETTERS, 4, 1);
+
+        let mut buffer = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut framebuffer = SoftFramebuffer::new(&mut buffer);
+
+        let end = framebuffer.draw_text(&FONT, "\u{0}\u{1}\u{0}", (10, 20).into(), 0x1f);
+        assert_eq!(end, (21, 20).into());
+
+        let end = framebuffer.draw_text(&FONT, "\u{1}\n\u{0}", (WIDTH - 2, 30).into(), 0x1f);
+        assert_eq!(end, (WIDTH + 1, 34).into(), "clipped at the right edge");
+
+        let row: [u16; 11] = core::array::from_fn(|x| buffer[(20 * WIDTH + 10) as usize + x]);
+        assert_eq!(
+            row,
+            [0x1f, 0x1f, 0, 0x1f, 0x1f, 0x1f, 0x1f, 0, 0x1f, 0x1f, 0]
+        );
+        assert_eq!(buffer[(30 * WIDTH + WIDTH - 1) as usize], 0x1f);
+        assert_eq!(buffer[(34 * WIDTH + WIDTH - 2) as usize], 0x1f);
+    }
+
+    #[test_case]
+    fn transformed_draws_in_rotated_space(_: &mut crate::Gba) {
+        use crate::fixnum::num;
+
+        let mut buffer = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut framebuffer = SoftFramebuffer::new(&mut buffer);
+        let mut transformed = Transformed::new(&mut framebuffer);
+
+        transformed.draw_point(3, 4, 0x7fff);
+
+        // rotate by a quarter turn, then move the origin to (50, 50)
+        transformed.set_transform(
+            AffineMatrix::from_translation((num!(-50.), num!(-50.)).into())
+                * AffineMatrix::from_rotation::<8>(num!(0.25)),
+        );
+        transformed.draw_line((0, 0).into(), (10, 0).into(), 0x1f);
+        transformed.draw_point(0, 5, 0x3e0);
+        transformed.draw_point(1000, 0, 0x3e0);
+
+        let pixel = |x: i32, y: i32| buffer[(y * WIDTH + x) as usize];
+
+        assert_eq!(pixel(3, 4), 0x7fff, "starts off as the identity");
+        assert!(
+            (50..=60).all(|y| pixel(50, y) == 0x1f),
+            "x axis points down"
+        );
+        assert_eq!(pixel(51, 50), 0);
+        assert_eq!(pixel(50, 61), 0);
+        assert_eq!(pixel(45, 50), 0x3e0, "y axis points left");
+    }
+}
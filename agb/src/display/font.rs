@@ -58,6 +58,38 @@ impl Font {
     fn letter(&self, letter: char) -> &'static FontLetter {
         &self.letters[letter as usize]
     }
+
+    /// The height of each line of text in pixels
+    pub(crate) fn line_height(&self) -> i32 {
+        self.line_height
+    }
+
+    /// Calls `plot` with the position of each pixel of `letter` when drawn
+    /// with the top left of the line at `cursor`, and returns how far the
+    /// cursor should then move along.
+    pub(crate) fn plot_letter(
+        &self,
+        letter: char,
+        cursor: Vector2D<i32>,
+        mut plot: impl FnMut(Vector2D<i32>),
+    ) -> i32 {
+        let letter = self.letter(letter);
+        let width = i32::from(letter.width);
+
+        let x_start = cursor.x + i32::from(letter.xmin);
+        let y_start = cursor.y + self.ascent - i32::from(letter.height) - i32::from(letter.ymin);
+
+        for y in 0..i32::from(letter.height) {
+            for x in 0..width {
+                let pos = x + y * width;
+                if (letter.data[(pos / 8) as usize] >> (pos & 7)) & 1 != 0 {
+                    plot((x_start + x, y_start + y).into());
+                }
+            }
+        }
+
+        i32::from(letter.advance_width)
+    }
 }
 
 impl Font {
@@ -1,4 +1,4 @@
-use crate::memory_mapped::MemoryMapped;
+use crate::{fixnum::Num, memory_mapped::MemoryMapped};
 use bitflags::bitflags;
 
 use modular_bitfield::BitfieldSpecifier;
@@ -10,12 +10,16 @@ use self::{blend::Blend, object::ObjectController, window::Windows};
 pub mod bitmap3;
 /// Graphics mode 4. Bitmap 4 provides two 8-bit paletted framebuffers with page switching.
 pub mod bitmap4;
+/// Drawing shapes to any of the bitmap modes.
+pub mod draw_target;
 /// Test logo of agb.
 pub mod example_logo;
 /// Implements sprites.
 pub mod object;
 /// Palette type.
 pub mod palette16;
+/// Commonly used display types, intended to be imported with `use agb::display::prelude::*`.
+pub mod prelude;
 /// Data produced by agb-image-converter
 pub mod tile_data;
 /// Graphics mode 0. Four regular backgrounds.
@@ -51,12 +55,69 @@ bitflags! {
     }
 }
 
+/// Packs red, green and blue channels, each in the range 0 to 31, into the 15
+/// bit colour format used by the Game Boy Advance. This is evaluated at compile
+/// time, and channels which are out of range are a compile error.
+///
+/// ```rust,no_run
+/// # #![no_std]
+/// # #![no_main]
+/// const RED: u16 = agb::rgb15!(31, 0, 0);
+/// ```
+#[macro_export]
+macro_rules! rgb15 {
+    ($r: expr, $g: expr, $b: expr) => {{
+        const COLOUR: u16 = {
+            let (r, g, b): (u16, u16, u16) = ($r, $g, $b);
+            assert!(
+                r < 32 && g < 32 && b < 32,
+                "colour channels must be in the range 0 to 31"
+            );
+            r | (g << 5) | (b << 10)
+        };
+        COLOUR
+    }};
+}
+
+#[must_use]
+/// Converts a colour given as hue, saturation and value to the 15 bit colour
+/// format used by the Game Boy Advance. The hue is in revolutions, so wraps
+/// around every 1, with 0 being red, 1/3 green and 2/3 blue. Saturation and
+/// value are clamped to the range 0 to 1.
+pub fn hsv_to_rgb15(h: Num<i32, 8>, s: Num<i32, 8>, v: Num<i32, 8>) -> u16 {
+    const ONE: i32 = 1 << 8;
+
+    let h = h.rem_euclid(1.into()).to_raw() * 6;
+    let s = s.to_raw().clamp(0, ONE);
+    let v = v.to_raw().clamp(0, ONE);
+
+    let sector = h / ONE;
+    let f = h % ONE;
+
+    let p = v * (ONE - s) / ONE;
+    let q = v * (ONE - s * f / ONE) / ONE;
+    let t = v * (ONE - s * (ONE - f) / ONE) / ONE;
+
+    let (r, g, b) = match sector {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+
+    let to_channel = |c: i32| ((c * 31 + ONE / 2) / ONE) as u16;
+    to_channel(r) | (to_channel(g) << 5) | (to_channel(b) << 10)
+}
+
 /// Width of the Gameboy advance screen in pixels
 pub const WIDTH: i32 = 240;
 /// Height of the Gameboy advance screen in pixels
 pub const HEIGHT: i32 = 160;
 
 #[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum DisplayMode {
     Tiled0 = 0,
     Tiled1 = 1,
@@ -133,6 +194,102 @@ unsafe fn set_graphics_settings(settings: GraphicsSettings) {
     DISPLAY_CONTROL.set(s);
 }
 
+fn current_graphics_mode() -> DisplayMode {
+    match DISPLAY_CONTROL.get() & 0b111 {
+        0 => DisplayMode::Tiled0,
+        1 => DisplayMode::Tiled1,
+        2 => DisplayMode::Tiled2,
+        3 => DisplayMode::Bitmap3,
+        4 => DisplayMode::Bitmap4,
+        5 => DisplayMode::Bitmap5,
+        mode => unreachable!("display mode {mode} is invalid"),
+    }
+}
+
+fn current_graphics_settings() -> GraphicsSettings {
+    GraphicsSettings::from_bits_truncate(DISPLAY_CONTROL.get())
+}
+
+/// Clears what is displayed in the current bitmap mode to colour 0, which is
+/// black in mode 3 and 5, or the first colour of the palette in mode 4. Only
+/// the page being displayed is cleared in the modes which have two. Tiled
+/// modes are left as they are, since clearing them depends on how the
+/// backgrounds are being used.
+pub fn clear_screen() {
+    const VRAM: usize = 0x0600_0000;
+    const BACK_PAGE_OFFSET: usize = 0xA000;
+
+    let page = if current_graphics_settings().contains(GraphicsSettings::PAGE_SELECT) {
+        BACK_PAGE_OFFSET
+    } else {
+        0
+    };
+
+    let (start, bytes) = match current_graphics_mode() {
+        DisplayMode::Bitmap3 => (VRAM, WIDTH * HEIGHT * 2),
+        DisplayMode::Bitmap4 => (VRAM + page, WIDTH * HEIGHT),
+        DisplayMode::Bitmap5 => (VRAM + page, 160 * 128 * 2),
+        DisplayMode::Tiled0 | DisplayMode::Tiled1 | DisplayMode::Tiled2 => return,
+    };
+
+    // video memory doesn't support writing single bytes, so this clears a
+    // word at a time
+    let start = start as *mut u32;
+    for i in 0..bytes as usize / 4 {
+        unsafe { start.add(i).write_volatile(0) };
+    }
+}
+
+#[must_use = "dropping the guard restores the previous display mode straight away"]
+/// Holds what was used to switch display mode, such as a
+/// [`Bitmap3`][bitmap3::Bitmap3], and restores the display mode, and which
+/// layers were enabled, to what they were before once it is dropped. This is
+/// returned by [`Video::bitmap3_with_guard`] for temporarily showing a bitmap,
+/// for example as an overlay, before going back to what was being displayed.
+/// The guard derefs to what it holds, so that can't be used after the mode has
+/// been restored.
+pub struct ModeGuard<T> {
+    inner: T,
+    mode: DisplayMode,
+    settings: GraphicsSettings,
+}
+
+impl<T> ModeGuard<T> {
+    fn new(enter: impl FnOnce() -> T) -> Self {
+        let mode = current_graphics_mode();
+        let settings = current_graphics_settings();
+
+        ModeGuard {
+            inner: enter(),
+            mode,
+            settings,
+        }
+    }
+}
+
+impl<T> core::ops::Deref for ModeGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> core::ops::DerefMut for ModeGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T> Drop for ModeGuard<T> {
+    fn drop(&mut self) {
+        unsafe {
+            set_graphics_mode(self.mode);
+            set_graphics_settings(self.settings);
+        }
+    }
+}
+
 #[allow(non_snake_case)]
 /// Waits until vblank using a busy wait loop, this should almost never be used.
 /// I only say almost because whilst I don't believe there to be a reason to use
@@ -149,3 +306,96 @@ pub enum Priority {
     P2 = 2,
     P3 = 3,
 }
+
+#[cfg(test)]
+mod tests {
+    #[test_case]
+    fn dropping_mode_guard_restores_previous_mode(gba: &mut crate::Gba) {
+        use super::{
+            current_graphics_mode, current_graphics_settings, set_graphics_mode,
+            set_graphics_settings, DisplayMode, GraphicsSettings,
+        };
+
+        let settings = GraphicsSettings::LAYER_BG0 | GraphicsSettings::LAYER_OBJ;
+        unsafe {
+            set_graphics_mode(DisplayMode::Tiled1);
+            set_graphics_settings(settings);
+        }
+
+        let mut bitmap = gba.display.video.bitmap3_with_guard();
+        assert_eq!(current_graphics_mode(), DisplayMode::Bitmap3);
+        bitmap.draw_point(1, 2, 0x1f);
+
+        drop(bitmap);
+        assert_eq!(current_graphics_mode(), DisplayMode::Tiled1);
+        assert_eq!(current_graphics_settings(), settings);
+    }
+
+    #[test_case]
+    fn clear_screen_zeroes_mode_3_buffer(gba: &mut crate::Gba) {
+        use super::{HEIGHT, WIDTH};
+        use crate::fixnum::Rect;
+        use alloc::vec;
+
+        let mut bitmap = gba.display.video.bitmap3();
+        bitmap.fill_rect(Rect::new((0, 0).into(), (WIDTH, HEIGHT).into()), 0x7fff);
+
+        super::clear_screen();
+
+        let mut screen = vec![0xffff; (WIDTH * HEIGHT) as usize];
+        bitmap.copy_to_slice(&mut screen);
+        assert!(screen.iter().all(|&colour| colour == 0));
+    }
+
+    #[test_case]
+    fn rgb15_packs_channels(_gba: &mut crate::Gba) {
+        assert_eq!(crate::rgb15!(31, 0, 0), 0x001f);
+        assert_eq!(crate::rgb15!(0, 0, 31), 0x7c00);
+        assert_eq!(crate::rgb15!(1, 2, 3), 1 | 2 << 5 | 3 << 10);
+    }
+
+    #[test_case]
+    fn hsv_to_rgb15_converts_known_colours(_gba: &mut crate::Gba) {
+        use super::hsv_to_rgb15;
+        use crate::fixnum::num;
+
+        let one = num!(1.);
+
+        assert_eq!(hsv_to_rgb15(num!(0.), one, one), crate::rgb15!(31, 0, 0));
+        assert_eq!(
+            hsv_to_rgb15(num!(0.3333), one, one),
+            crate::rgb15!(0, 31, 0)
+        );
+        assert_eq!(
+            hsv_to_rgb15(num!(0.6667), one, one),
+            crate::rgb15!(0, 0, 31)
+        );
+        assert_eq!(hsv_to_rgb15(num!(0.5), one, one), crate::rgb15!(0, 31, 31));
+        assert_eq!(
+            hsv_to_rgb15(num!(0.5), one, num!(0.5)),
+            crate::rgb15!(0, 16, 16)
+        );
+
+        assert_eq!(
+            hsv_to_rgb15(num!(0.25), num!(0.), one),
+            crate::rgb15!(31, 31, 31)
+        );
+        assert_eq!(hsv_to_rgb15(num!(0.25), one, num!(0.)), 0);
+
+        assert_eq!(
+            hsv_to_rgb15(num!(1.5), one, one),
+            crate::rgb15!(0, 31, 31),
+            "hue wraps"
+        );
+        assert_eq!(
+            hsv_to_rgb15(num!(-0.5), one, one),
+            crate::rgb15!(0, 31, 31),
+            "hue wraps"
+        );
+        assert_eq!(
+            hsv_to_rgb15(num!(0.), num!(2.), num!(3.)),
+            crate::rgb15!(31, 0, 0),
+            "clamped"
+        );
+    }
+}
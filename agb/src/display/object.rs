@@ -14,6 +14,7 @@ use modular_bitfield::{bitfield, BitfieldSpecifier};
 
 const BYTES_PER_TILE_4BPP: usize = 32;
 
+use super::affine::AffineSlotAllocator;
 use super::palette16::Palette16;
 use super::{Priority, DISPLAY_CONTROL};
 use crate::agb_alloc::block_allocator::BlockAllocator;
@@ -667,7 +668,7 @@ struct ObjectInner {
 }
 
 struct ObjectControllerStatic {
-    _free_affine_matrices: Vec<u8>,
+    free_affine_matrices: Vec<u8>,
     free_object: Vec<u8>,
     shadow_oam: Vec<Option<ObjectInner>>,
     z_order: Vec<u8>,
@@ -680,7 +681,7 @@ impl ObjectControllerStatic {
             shadow_oam: (0..128).map(|_| None).collect(),
             z_order: (0..128).collect(),
             free_object: (0..128).collect(),
-            _free_affine_matrices: (0..32).collect(),
+            free_affine_matrices: (0..32).collect(),
             sprite_controller: SpriteControllerInner::new(),
         }
     }
@@ -947,6 +948,27 @@ impl ObjectController {
             .sprite_controller
             .try_get_sprite(sprite)
     }
+
+    #[must_use]
+    /// Creates an [AffineSlotAllocator] for handing out the
+    /// [AFFINE_SLOT_COUNT](super::affine::AFFINE_SLOT_COUNT) affine matrix
+    /// slots. Every allocator takes from the same slots, which this controller
+    /// owns, so matrices written through them never overwrite each other.
+    pub fn affine_slots(&self) -> AffineSlotAllocator {
+        AffineSlotAllocator::new(self)
+    }
+
+    pub(crate) fn take_affine_slot(&self) -> Option<u8> {
+        unsafe { self.inner.borrow_mut() }
+            .free_affine_matrices
+            .pop()
+    }
+
+    pub(crate) fn return_affine_slot(&self, index: u8) {
+        unsafe { self.inner.borrow_mut() }
+            .free_affine_matrices
+            .push(index);
+    }
 }
 
 impl<'a> Object<'a> {
@@ -0,0 +1,4 @@
+pub use super::affine::{AffineMatrix, AffineMatrixBackground, AffineMatrixObject};
+pub use super::bitmap3::Bitmap3;
+pub use super::draw_target::DrawTarget;
+pub use crate::fixnum::Rect;
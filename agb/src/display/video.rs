@@ -2,6 +2,7 @@ use super::{
     bitmap3::Bitmap3,
     bitmap4::Bitmap4,
     tiled::{Tiled0, Tiled1, Tiled2, VRamManager},
+    ModeGuard,
 };
 
 /// The video struct controls access to the video hardware.
@@ -14,7 +15,14 @@ pub struct Video;
 impl Video {
     /// Bitmap mode that provides a 16-bit colour framebuffer
     pub fn bitmap3(&mut self) -> Bitmap3 {
-        unsafe { Bitmap3::new() }
+        Bitmap3::acquire(self)
+    }
+
+    /// The same as [`bitmap3`][Video::bitmap3], but with the bitmap held by a
+    /// guard which goes back to the display mode from before when it is
+    /// dropped
+    pub fn bitmap3_with_guard(&mut self) -> ModeGuard<Bitmap3> {
+        ModeGuard::new(|| self.bitmap3())
     }
 
     /// Bitmap 4 provides two 8-bit paletted framebuffers with page switching
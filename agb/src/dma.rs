@@ -12,6 +12,10 @@ const fn dma_control_addr(dma: usize) -> usize {
     0x0400_00b8 + 0x0c * dma
 }
 
+const DMA0_SOURCE_ADDR: MemoryMapped<u32> = unsafe { MemoryMapped::new(dma_source_addr(0)) };
+const DMA0_DEST_ADDR: MemoryMapped<u32> = unsafe { MemoryMapped::new(dma_dest_addr(0)) };
+const DMA0_CONTROL: MemoryMapped<u32> = unsafe { MemoryMapped::new(dma_control_addr(0)) };
+
 const DMA3_SOURCE_ADDR: MemoryMapped<u32> = unsafe { MemoryMapped::new(dma_source_addr(3)) };
 const DMA3_DEST_ADDR: MemoryMapped<u32> = unsafe { MemoryMapped::new(dma_dest_addr(3)) };
 const DMA3_CONTROL: MemoryMapped<u32> = unsafe { MemoryMapped::new(dma_control_addr(3)) };
@@ -25,6 +29,37 @@ pub(crate) unsafe fn dma_copy16(src: *const u16, dest: *mut u16, count: usize) {
     DMA3_CONTROL.set(count as u32 | (1 << 31));
 }
 
+/// Sets up DMA 0 to copy `count` words from `src` to `dest` at every
+/// horizontal blank of the visible rows. The source carries on from where it
+/// got to each time, while `dest` goes back to where it started if
+/// `reload_dest` is set, which is how the same registers are rewritten every
+/// row, or otherwise carries on too.
+pub(crate) unsafe fn hblank_dma0_copy32(
+    src: *const u32,
+    dest: *mut u32,
+    count: u16,
+    reload_dest: bool,
+) {
+    let dest_control: u32 = if reload_dest { 3 << 5 } else { 0 };
+    let repeat: u32 = 1 << 9;
+    let transfer_type: u32 = 1 << 10; // transfer in words
+    let dma_start_timing: u32 = 2 << 12; // horizontal blank
+    let enable: u32 = 1 << 15;
+
+    DMA0_CONTROL.set(0);
+    DMA0_SOURCE_ADDR.set(src as u32);
+    DMA0_DEST_ADDR.set(dest as u32);
+    DMA0_CONTROL.set(
+        u32::from(count)
+            | ((dest_control | repeat | transfer_type | dma_start_timing | enable) << 16),
+    );
+}
+
+/// Stops any transfer set up on DMA 0
+pub(crate) fn stop_dma0() {
+    DMA0_CONTROL.set(0);
+}
+
 pub(crate) fn dma3_exclusive<R>(f: impl FnOnce() -> R) -> R {
     const DMA0_CTRL_HI: MemoryMapped<u16> = unsafe { MemoryMapped::new(dma_control_addr(0) + 2) };
     const DMA1_CTRL_HI: MemoryMapped<u16> = unsafe { MemoryMapped::new(dma_control_addr(1) + 2) };
@@ -49,4 +84,4 @@ pub(crate) fn dma3_exclusive<R>(f: impl FnOnce() -> R) -> R {
         // returns the return value
         ret
     })
-}
\ No newline at end of file
+}
@@ -147,9 +147,25 @@ macro_rules! include_font {
 /// ```
 pub use agb_macros::entry;
 
+/// Registers a test like `#[test_case]`, but can also give it a maximum number
+/// of cycles it may take, failing it in `mgba-test-runner` if it takes longer.
+/// See the [test_runner] module for how to set up tests.
+///
+/// ```rust,ignore
+/// #[agb::test_case(max_cycles = 10_000)]
+/// fn test_multiply_is_fast(_gba: &mut Gba) {
+///     let a = AffineMatrix::from_rotation::<8>(num!(0.125));
+///     let b = a * a;
+/// }
+/// ```
+#[cfg(any(test, feature = "testing"))]
+pub use agb_macros::test_case;
+
 pub use agb_sound_converter::include_wav;
 
 extern crate alloc;
+// the paths the macros generate start with agb, which need to work in agb too
+extern crate self as agb;
 mod agb_alloc;
 
 mod agbabi;
@@ -287,25 +303,50 @@ pub mod test_runner {
         T: Fn(&mut Gba),
     {
         fn run(&self, gba: &mut Gba) {
-            let mut mgba = mgba::Mgba::new().unwrap();
+            run_test(core::any::type_name::<T>(), None, gba, self);
+        }
+    }
+
+    /// A test registered by [`#[agb::test_case(max_cycles = ...)]`][crate::test_case]
+    /// along with the number of cycles it may take
+    #[doc(hidden)]
+    pub struct CycleBudgeted {
+        pub name: &'static str,
+        pub test: fn(&mut Gba),
+        pub max_cycles: u32,
+    }
+
+    impl Testable for CycleBudgeted {
+        fn run(&self, gba: &mut Gba) {
+            run_test(self.name, Some(self.max_cycles), gba, self.test);
+        }
+    }
+
+    fn run_test(name: &str, max_cycles: Option<u32>, gba: &mut Gba, test: impl Fn(&mut Gba)) {
+        let mut mgba = mgba::Mgba::new().unwrap();
+        mgba.print(format_args!("{name}..."), mgba::DebugLevel::Info)
+            .unwrap();
+        // mgba-test-runner compares this with the cycles between the tags
+        if let Some(max_cycles) = max_cycles {
             mgba.print(
-                format_args!("{}...", core::any::type_name::<T>()),
+                format_args!("Cycle budget: {max_cycles}"),
                 mgba::DebugLevel::Info,
             )
             .unwrap();
-            mgba::number_of_cycles_tagged(785);
-            self(gba);
-            mgba::number_of_cycles_tagged(785);
+        }
 
-            assert!(
-                unsafe { agb_alloc::number_of_blocks() } < 2,
-                "memory is being leaked, there are {} blocks",
-                unsafe { agb_alloc::number_of_blocks() }
-            );
+        mgba::number_of_cycles_tagged(785);
+        test(gba);
+        mgba::number_of_cycles_tagged(785);
 
-            mgba.print(format_args!("[ok]"), mgba::DebugLevel::Info)
-                .unwrap();
-        }
+        assert!(
+            unsafe { agb_alloc::number_of_blocks() } < 2,
+            "memory is being leaked, there are {} blocks",
+            unsafe { agb_alloc::number_of_blocks() }
+        );
+
+        mgba.print(format_args!("[ok]"), mgba::DebugLevel::Info)
+            .unwrap();
     }
 
     #[panic_handler]
@@ -366,6 +407,34 @@ pub mod test_runner {
         loop {}
     }
 
+    /// Implemented for types which can be compared using [assert_approx_eq],
+    /// for checking results which have fixed point rounding errors.
+    pub trait ApproxEq {
+        /// The type of the tolerance of the comparison
+        type Epsilon: Copy + core::fmt::Debug;
+
+        /// Whether `self` and `other` differ by at most `epsilon`
+        fn approx_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool;
+    }
+
+    impl<I: fixnum::FixedWidthSignedInteger, const N: usize> ApproxEq for fixnum::Num<I, N> {
+        type Epsilon = Self;
+
+        fn approx_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+            (*self - *other).abs() <= epsilon
+        }
+    }
+
+    impl<I: fixnum::FixedWidthSignedInteger, const N: usize> ApproxEq
+        for fixnum::Vector2D<fixnum::Num<I, N>>
+    {
+        type Epsilon = fixnum::Num<I, N>;
+
+        fn approx_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+            self.x.approx_eq(&other.x, epsilon) && self.y.approx_eq(&other.y, epsilon)
+        }
+    }
+
     pub fn assert_image_output(image: &str) {
         display::busy_wait_for_vblank();
         display::busy_wait_for_vblank();
@@ -376,6 +445,36 @@ pub mod test_runner {
     }
 }
 
+/// Asserts that two values are equal to within a tolerance, which is useful in
+/// tests of fixed point calculations where results can be slightly off due to
+/// rounding. This works with any type implementing [test_runner::ApproxEq],
+/// which includes [Num][fixnum::Num], [Vector2D][fixnum::Vector2D] and
+/// [AffineMatrix][display::affine::AffineMatrix].
+///
+/// ```rust,ignore
+/// #[test_case]
+/// fn test_half_turn(_gba: &mut Gba) {
+///     let half_turn = AffineMatrix::from_rotation::<8>(num!(0.5));
+///     assert_approx_eq!(half_turn * half_turn, AffineMatrix::identity(), num!(0.01));
+/// }
+/// ```
+#[macro_export]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr, $epsilon:expr $(,)?) => {
+        match (&$left, &$right, $epsilon) {
+            (left, right, epsilon) => {
+                assert!(
+                    $crate::test_runner::ApproxEq::approx_eq(left, right, epsilon),
+                    "assertion failed: `left ≈ right` to within `{:?}`\n  left: `{:?}`\n right: `{:?}`",
+                    epsilon,
+                    left,
+                    right,
+                );
+            }
+        }
+    };
+}
+
 #[cfg(test)]
 mod test {
     use super::Gba;
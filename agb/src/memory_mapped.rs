@@ -1,4 +1,4 @@
-use core::ops;
+use core::{marker::PhantomData, ops};
 
 pub struct MemoryMapped<T> {
     address: *mut T,
@@ -96,4 +96,167 @@ impl<T, const X: usize, const Y: usize> MemoryMapped2DArray<T, X, Y> {
     pub fn set(&self, x: usize, y: usize, val: T) {
         unsafe { (&mut (*self.array)[y][x] as *mut T).write_volatile(val) }
     }
+
+    pub fn as_ptr(&self) -> *mut T {
+        self.array.cast()
+    }
+
+    /// Lazily reads the values in the given rectangle in row major order.
+    pub fn region(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> impl Iterator<Item = T> {
+        assert!(
+            x + width <= X && y + height <= Y,
+            "region out of bounds of the array"
+        );
+
+        let array = self.array;
+        (y..y + height).flat_map(move |y| {
+            (x..x + width).map(move |x| unsafe { (&mut (*array)[y][x] as *mut T).read_volatile() })
+        })
+    }
+}
+
+#[allow(dead_code)]
+impl<T: PartialEq, const X: usize, const Y: usize> MemoryMapped2DArray<T, X, Y> {
+    /// Writes the value only if it differs from what is already there,
+    /// returning whether it was written. The read costs about as much as a
+    /// write, so this only helps when most writes wouldn't change anything.
+    pub fn set_if_changed(&self, x: usize, y: usize, val: T) -> bool {
+        if self.get(x, y) == val {
+            false
+        } else {
+            self.set(x, y, val);
+            true
+        }
+    }
+}
+
+/// Integers which can be converted to a fixed byte order. [`set`] writes
+/// values in the native byte order, which on the GBA is little endian.
+///
+/// [`set`]: MemoryMapped2DArray::set
+pub trait Integer: Copy {
+    fn to_le(self) -> Self;
+    fn to_be(self) -> Self;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                fn to_le(self) -> Self {
+                    <$t>::to_le(self)
+                }
+
+                fn to_be(self) -> Self {
+                    <$t>::to_be(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+#[allow(dead_code)]
+impl<T: Integer, const X: usize, const Y: usize> MemoryMapped2DArray<T, X, Y> {
+    /// Writes the value so that its least significant byte is at the lowest
+    /// address, whatever the byte order of the machine running the code.
+    pub fn set_le(&self, x: usize, y: usize, val: T) {
+        self.set(x, y, val.to_le());
+    }
+
+    /// Writes the value so that its most significant byte is at the lowest
+    /// address, for data which is read a byte at a time in big endian order.
+    pub fn set_be(&self, x: usize, y: usize, val: T) {
+        self.set(x, y, val.to_be());
+    }
+}
+
+/// Two pages of the same shape at fixed addresses, one of which is being
+/// displayed while the other is drawn to, keeping track of which is which.
+pub struct PagedMemoryMapped2DArray<T, const X: usize, const Y: usize> {
+    pages: [usize; 2],
+    front: usize,
+    phantom: PhantomData<T>,
+}
+
+#[allow(dead_code)]
+impl<T, const X: usize, const Y: usize> PagedMemoryMapped2DArray<T, X, Y> {
+    /// The page at `front` starts off as the front page. Both addresses must be
+    /// valid for a [MemoryMapped2DArray] of this shape.
+    pub const unsafe fn new(front: usize, back: usize) -> Self {
+        PagedMemoryMapped2DArray {
+            pages: [front, back],
+            front: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Swaps the front and back pages.
+    pub fn flip(&mut self) {
+        self.front ^= 1;
+    }
+
+    pub fn front(&self) -> MemoryMapped2DArray<T, X, Y> {
+        unsafe { MemoryMapped2DArray::new(self.pages[self.front]) }
+    }
+
+    pub fn back(&self) -> MemoryMapped2DArray<T, X, Y> {
+        unsafe { MemoryMapped2DArray::new(self.pages[self.front ^ 1]) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryMapped2DArray, PagedMemoryMapped2DArray};
+
+    #[test_case]
+    fn set_le_and_set_be_fix_byte_order(_: &mut crate::Gba) {
+        let mut backing = [[0u32; 2]; 1];
+        let array: MemoryMapped2DArray<u32, 2, 1> =
+            unsafe { MemoryMapped2DArray::new(backing.as_mut_ptr() as usize) };
+
+        array.set_le(0, 0, 0x1234_5678);
+        array.set_be(1, 0, 0x1234_5678);
+
+        let bytes: [u8; 8] = unsafe { core::mem::transmute(backing) };
+        assert_eq!(bytes, [0x78, 0x56, 0x34, 0x12, 0x12, 0x34, 0x56, 0x78]);
+    }
+
+    #[test_case]
+    fn set_if_changed_skips_unchanged_values(_: &mut crate::Gba) {
+        let mut backing = [[0u16; 4]; 2];
+        let array: MemoryMapped2DArray<u16, 4, 2> =
+            unsafe { MemoryMapped2DArray::new(backing.as_mut_ptr() as usize) };
+
+        assert!(array.set_if_changed(1, 1, 5));
+        assert_eq!(array.get(1, 1), 5);
+        assert!(!array.set_if_changed(1, 1, 5));
+        assert!(!array.set_if_changed(0, 0, 0));
+        assert!(array.set_if_changed(1, 1, 6));
+
+        assert_eq!(backing, [[0, 0, 0, 0], [0, 6, 0, 0]]);
+    }
+
+    #[test_case]
+    fn flip_swaps_back_page(_: &mut crate::Gba) {
+        let mut pages: PagedMemoryMapped2DArray<u16, 120, 160> =
+            unsafe { PagedMemoryMapped2DArray::new(0x600_0000, 0x600_A000) };
+
+        assert_eq!(pages.front().as_ptr() as usize, 0x600_0000);
+        assert_eq!(pages.back().as_ptr() as usize, 0x600_A000);
+
+        pages.flip();
+        assert_eq!(pages.front().as_ptr() as usize, 0x600_A000);
+        assert_eq!(pages.back().as_ptr() as usize, 0x600_0000);
+
+        pages.flip();
+        assert_eq!(pages.back().as_ptr() as usize, 0x600_A000);
+    }
 }
@@ -26,6 +26,21 @@ fn is_running_in_mgba() -> bool {
     DEBUG_ENABLE.get() == ENABLE_HANDSHAKE_OUT
 }
 
+#[must_use]
+/// Whether agb is built to run its own tests in `mgba-test-runner`. This is
+/// only the case for `cargo test`, so it is `false` in every game, including
+/// those built with the default `testing` feature. It is known at compile
+/// time, so emulator only diagnostics behind it are removed entirely from
+/// normal builds.
+pub const fn is_under_test_runner() -> bool {
+    cfg!(test)
+}
+
+// checked in every normal build rather than by a test, since tests are the one
+// place where it is true
+#[cfg(not(test))]
+const _: () = assert!(!is_under_test_runner());
+
 const NUMBER_OF_CYCLES: MemoryMapped<u16> = unsafe { MemoryMapped::new(0x04FF_F800) };
 
 pub fn number_of_cycles_tagged(tag: u16) {
@@ -90,3 +105,14 @@ macro_rules! println {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_is_under_test_runner(_: &mut crate::Gba) {
+        const UNDER_TEST_RUNNER: bool = is_under_test_runner();
+        assert!(UNDER_TEST_RUNNER);
+    }
+}
@@ -1,5 +1,7 @@
 #include "test-runner.h"
 
+#include <fcntl.h>
+#include <mgba-util/vfs.h>
 #include <mgba/core/core.h>
 #include <mgba/feature/commandline.h>
 #include <stdio.h>
@@ -62,6 +64,23 @@ struct MGBA* new_runner(char* filename) {
     return mgba;
 }
 
+int load_bios(struct MGBA* mgba, char* filename) {
+    struct VFile* bios = VFileOpen(filename, O_RDONLY);
+    if (!bios) {
+        return 0;
+    }
+
+    if (!mgba->core->loadBIOS(mgba->core, bios, 0)) {
+        bios->close(bios);
+        return 0;
+    }
+
+    // reset so that the core boots using the bios which has just been loaded
+    mgba->core->reset(mgba->core);
+
+    return 1;
+}
+
 void set_logger(struct MGBA* mgba, struct callback callback) {
     mgba->callback = callback;
 }
@@ -18,6 +18,7 @@ struct callback {
 
 struct MGBA* new_runner(char filename[]);
 void free_runner(struct MGBA* mgba);
+int load_bios(struct MGBA* mgba, char filename[]);
 void set_logger(struct MGBA*, struct callback);
 void advance_frame(struct MGBA* mgba);
 struct video_buffer get_video_buffer(struct MGBA* mgba);
\ No newline at end of file
@@ -319,6 +319,12 @@ extern "C" {
 extern "C" {
     pub fn free_runner(mgba: *mut MGBA);
 }
+extern "C" {
+    pub fn load_bios(
+        mgba: *mut MGBA,
+        filename: *mut ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+}
 extern "C" {
     pub fn set_logger(arg1: *mut MGBA, arg2: callback);
 }
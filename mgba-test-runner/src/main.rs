@@ -27,14 +27,24 @@ enum Timing {
 
 const TEST_RUNNER_TAG: u16 = 785;
 
+/// The environment variable which can be set to the path of a GBA BIOS, which
+/// is then used instead of mgba's high level emulation of the BIOS
+const BIOS_ENV_VAR: &str = "MGBA_TEST_RUNNER_BIOS";
+
 fn test_file(file_to_run: &str) -> Status {
     let mut finished = Status::Running;
     let debug_reader_mutex = Regex::new(r"(?s)^\[(.*)\] GBA Debug: (.*)$").unwrap();
     let tagged_cycles_reader = Regex::new(r"Cycles: (\d*) Tag: (\d*)").unwrap();
+    let cycle_budget_reader = Regex::new(r"Cycle budget: (\d*)").unwrap();
 
     let mut mgba = runner::MGBA::new(file_to_run).unwrap();
+    if let Ok(bios) = std::env::var(BIOS_ENV_VAR) {
+        mgba.load_bios(&bios).unwrap();
+    }
     let video_buffer = mgba.get_video_buffer();
     let mut number_of_cycles = Timing::None;
+    // the maximum number of cycles the current test may take, if it has one
+    let mut cycle_budget = None;
 
     mgba.set_logger(|message| {
         if let Some(captures) = debug_reader_mutex.captures(message) {
@@ -54,6 +64,9 @@ fn test_file(file_to_run: &str) -> Status {
             } else if out.ends_with("...") {
                 print!("{}", out);
                 io::stdout().flush().expect("can't flush stdout");
+                cycle_budget = None;
+            } else if let Some(captures) = cycle_budget_reader.captures(out) {
+                cycle_budget = captures[1].parse::<i32>().ok();
             } else if out.starts_with("Cycles: ") {
                 if let Some(captures) = tagged_cycles_reader.captures(out) {
                     let num_cycles: i32 = captures[1].parse().unwrap();
@@ -68,7 +81,21 @@ fn test_file(file_to_run: &str) -> Status {
                     }
                 }
             } else if out == "[ok]" {
-                if let Timing::Difference(cycles) = number_of_cycles {
+                let over_budget = match (&number_of_cycles, cycle_budget) {
+                    (Timing::Difference(cycles), Some(budget)) if *cycles > budget => {
+                        Some((*cycles, budget))
+                    }
+                    _ => None,
+                };
+
+                if let Some((cycles, budget)) = over_budget {
+                    println!("[failed]");
+                    println!(
+                        "took {} cycles, which is over the budget of {}",
+                        cycles, budget
+                    );
+                    finished = Status::Failed;
+                } else if let Timing::Difference(cycles) = number_of_cycles {
                     println!(
                         "[ok: {} c ≈ {} s]",
                         cycles,
@@ -85,7 +112,8 @@ fn test_file(file_to_run: &str) -> Status {
                 finished = Status::Failed;
             }
 
-            if out == "Tests finished successfully" {
+            // a failure earlier in the same frame mustn't be overwritten
+            if out == "Tests finished successfully" && finished == Status::Running {
                 finished = Status::Success;
             }
         }
@@ -102,21 +130,57 @@ fn test_file(file_to_run: &str) -> Status {
 }
 
 fn main() -> Result<(), Error> {
-    let args: Vec<String> = std::env::args().collect();
-    let file_to_run = args.get(1).expect("you should provide file to run");
+    let files_to_run: Vec<String> = std::env::args().skip(1).collect();
+    if files_to_run.is_empty() {
+        return Err(anyhow!("you should provide at least one file to run"));
+    }
 
-    if !Path::new(file_to_run).exists() {
-        return Err(anyhow!("File to run should exist!"));
+    if let Some(missing) = files_to_run.iter().find(|file| !Path::new(file).exists()) {
+        return Err(anyhow!("File to run should exist! {} does not", missing));
+    }
+
+    if let Ok(bios) = std::env::var(BIOS_ENV_VAR) {
+        if !Path::new(&bios).exists() {
+            return Err(anyhow!(
+                "{} is set to {} which does not exist",
+                BIOS_ENV_VAR,
+                bios
+            ));
+        }
     }
 
-    let output = test_file(file_to_run);
+    let mut failed = Vec::new();
 
-    match output {
-        Status::Failed => Err(anyhow!("Tests failed!")),
-        Status::Success => Ok(()),
-        _ => {
-            unreachable!("very bad thing happened");
+    for file_to_run in &files_to_run {
+        if files_to_run.len() > 1 {
+            println!("Running tests in {}", file_to_run);
         }
+
+        match test_file(file_to_run) {
+            Status::Failed => failed.push(file_to_run),
+            Status::Success => {}
+            _ => {
+                unreachable!("very bad thing happened");
+            }
+        }
+    }
+
+    if files_to_run.len() > 1 {
+        println!(
+            "{} of {} test binaries passed",
+            files_to_run.len() - failed.len(),
+            files_to_run.len()
+        );
+
+        for file in &failed {
+            println!("[failed] {}", file);
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("Tests failed!"))
     }
 }
 
@@ -45,6 +45,15 @@ impl MGBA {
         }
     }
 
+    pub fn load_bios(&mut self, filename: &str) -> Result<(), anyhow::Error> {
+        let c_str = CString::new(filename).expect("should be able to make cstring from filename");
+        if unsafe { bindings::load_bios(self.mgba, c_str.as_ptr() as *mut c_char) } == 0 {
+            Err(anyhow::anyhow!("could not load bios {}", filename))
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn get_video_buffer(&self) -> VideoBuffer {
         let c_video_buffer = unsafe { bindings::get_video_buffer(self.mgba) };
         VideoBuffer {